@@ -0,0 +1,34 @@
+//! Detects the cfgs that the compiler building the library can check
+
+use std::env;
+use std::process::Command;
+
+/// The minor version of the rustc building the library, or `None` if it cannot be determined
+fn rustc_minor_version() -> Option<u32> {
+    let rustc = env::var_os("RUSTC")?;
+    let out = Command::new(rustc).arg("--version").output().ok()?;
+    let version = String::from_utf8(out.stdout).ok()?;
+
+    version
+        .strip_prefix("rustc 1.")?
+        .split('.')
+        .next()?
+        .parse()
+        .ok()
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let minor = rustc_minor_version().unwrap_or(0);
+
+    // Cargo checks the names of cfgs since Rust 1.80
+    if minor >= 80 {
+        println!("cargo:rustc-check-cfg=cfg(has_target_abi)");
+    }
+
+    // `cfg(target_abi)` is stable since Rust 1.78
+    if minor >= 78 {
+        println!("cargo:rustc-cfg=has_target_abi");
+    }
+}
@@ -0,0 +1,327 @@
+//! Expansion of legacy machine names, such as `sun4` or `pc98`, in the manner of GNU config.sub
+//!
+//! GNU config.sub accepts a number of historical names for machines, which stand in for the
+//! architecture and vendor (and sometimes the operating system) of a target.
+//! This module contains a port of the `basic_machine` table of the config.sub script shipped with this library,
+//!  restricted to machines that can be represented by the pieces of a [`CanonicalTarget`].
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use crate::{pieces::*, CanonicalTarget};
+
+///
+/// A legacy machine name recognized by GNU config.sub
+#[derive(Copy, Clone, Debug)]
+pub struct MachineAlias {
+    /// The spellings of the machine
+    pub names: &'static [&'static str],
+    /// The architecture the machine expands to
+    pub arch: Architecture,
+    /// The vendor the machine expands to. config.sub gives no vendor to a machine it reads as an architecture, such as `w65`
+    pub vendor: Option<Vendor>,
+    /// The system implied by the machine. If [`None`], the default system for the architecture and vendor is used
+    pub sys: Option<System>,
+    /// The version config.sub gives the operating system implied by the machine, such as `4` for `irix4`
+    pub os_version: Option<&'static str>,
+}
+
+const fn alias(names: &'static [&'static str], arch: Architecture, vendor: Vendor) -> MachineAlias {
+    MachineAlias {
+        names,
+        arch,
+        vendor: Some(vendor),
+        sys: None,
+        os_version: None,
+    }
+}
+
+const fn alias_os(
+    names: &'static [&'static str],
+    arch: Architecture,
+    vendor: Vendor,
+    os: OS,
+) -> MachineAlias {
+    MachineAlias {
+        names,
+        arch,
+        vendor: Some(vendor),
+        sys: Some(System::from_os(os)),
+        os_version: None,
+    }
+}
+
+const fn alias_os_version(
+    names: &'static [&'static str],
+    arch: Architecture,
+    vendor: Vendor,
+    os: OS,
+    version: &'static str,
+) -> MachineAlias {
+    MachineAlias {
+        os_version: Some(version),
+        ..alias_os(names, arch, vendor, os)
+    }
+}
+
+/// The legacy machine names known to the library
+pub static BASIC_MACHINES: &[MachineAlias] = &[
+    alias(&["amiga"], Architecture::M68k, Vendor::Unknown),
+    alias_os(
+        &["amigaos", "amigados"],
+        Architecture::M68k,
+        Vendor::Unknown,
+        OS::AmigaOS,
+    ),
+    alias(
+        &[
+            "delta",
+            "3300",
+            "motorola-3300",
+            "motorola-delta",
+            "3300-motorola",
+            "delta-motorola",
+        ],
+        Architecture::M68k,
+        Vendor::Motorola,
+    ),
+    alias(
+        &[
+            "decstation",
+            "decstation-3100",
+            "pmax",
+            "pmin",
+            "dec3100",
+            "decstatn",
+        ],
+        Architecture::Mips,
+        Vendor::DEC,
+    ),
+    alias_os_version(
+        &["iris", "iris4d"],
+        Architecture::Mips,
+        Vendor::SGI,
+        OS::IRIX,
+        "4",
+    ),
+    alias(&["pc98"], Architecture::X86_32(3), Vendor::PC),
+    alias(
+        &["pentium", "p5", "k5", "k6", "nexgen", "viac3"],
+        Architecture::X86_32(5),
+        Vendor::PC,
+    ),
+    alias(
+        &[
+            "pentiumpro",
+            "p6",
+            "6x86",
+            "athlon",
+            "pentiumii",
+            "pentium2",
+            "pentiumiii",
+            "pentium3",
+        ],
+        Architecture::X86_32(6),
+        Vendor::PC,
+    ),
+    alias(&["pentium4"], Architecture::X86_32(7), Vendor::PC),
+    alias(&["x64"], Architecture::X86_64 { microarch: 1 }, Vendor::PC),
+    alias(&["ppc", "ppcbe"], Architecture::PowerPC32, Vendor::Unknown),
+    alias(&["ppc64"], Architecture::PowerPC64, Vendor::Unknown),
+    alias(
+        &["ppc64le", "powerpc64little"],
+        Architecture::PowerPC64le,
+        Vendor::Unknown,
+    ),
+    alias(
+        &["sun386", "sun386i", "roadrunner"],
+        Architecture::X86_32(3),
+        Vendor::Sun,
+    ),
+    alias(&["sun2", "sun3"], Architecture::M68k, Vendor::Sun),
+    alias_os_version(
+        &["sun2os3", "sun3os3"],
+        Architecture::M68k,
+        Vendor::Sun,
+        OS::SunOS,
+        "3",
+    ),
+    alias_os_version(
+        &["sun2os4", "sun3os4"],
+        Architecture::M68k,
+        Vendor::Sun,
+        OS::SunOS,
+        "4",
+    ),
+    alias(&["sun4"], Architecture::Sparc, Vendor::Sun),
+    alias_os_version(
+        &["sun4os3"],
+        Architecture::Sparc,
+        Vendor::Sun,
+        OS::SunOS,
+        "3",
+    ),
+    alias_os_version(
+        &["sun4os4"],
+        Architecture::Sparc,
+        Vendor::Sun,
+        OS::SunOS,
+        "4",
+    ),
+    alias_os(&["sun4sol2"], Architecture::Sparc, Vendor::Sun, OS::Solaris),
+    // config.sub reads `w65` on its own as an architecture, rather than as the `w65-wdc` machine
+    MachineAlias {
+        names: &["w65"],
+        arch: Architecture::Wc65c816,
+        vendor: None,
+        sys: Some(System::from_os(OS::None)),
+        os_version: None,
+    },
+];
+
+/// Finds the legacy machine with the given name
+pub fn find_machine(name: &str) -> Option<&'static MachineAlias> {
+    BASIC_MACHINES
+        .iter()
+        .find(|machine| machine.names.contains(&name))
+}
+
+///
+/// Returns the system GNU config.sub assumes for a machine when no operating system is given
+pub fn default_system(arch: Architecture, vendor: Vendor) -> System {
+    match (arch, vendor) {
+        (_, Vendor::DEC) => System::from_os(OS::Ultrix),
+        (
+            Architecture::Mips
+            | Architecture::MipsLE
+            | Architecture::Mips64
+            | Architecture::Mips64LE,
+            _,
+        ) => System::from_objfmt(ObjectFormat::Elf),
+        (Architecture::Sparc, _) | (_, Vendor::Sun) => System::from_os(OS::SunOS),
+        (_, Vendor::IBM) => System::from_os(OS::AIX),
+        (_, Vendor::HP) => System::from_os(OS::HPUX),
+        (_, Vendor::Motorola) => System::from_os(OS::SysV),
+        (_, Vendor::Commodore) => System::from_os(OS::AmigaOS),
+        (_, Vendor::SGI) => System::from_os(OS::IRIX),
+        (_, Vendor::Apple) => System::from_os(OS::MacOSX),
+        _ => System::from_os(OS::None),
+    }
+}
+
+///
+/// Returns the version config.sub gives the system returned by [`default_system`], such as `4.1.1` for `sunos4.1.1`
+pub fn default_os_version(arch: Architecture, vendor: Vendor) -> Option<&'static str> {
+    match (arch, vendor) {
+        (_, Vendor::DEC) => Some("4.2"),
+        (
+            Architecture::Mips
+            | Architecture::MipsLE
+            | Architecture::Mips64
+            | Architecture::Mips64LE,
+            _,
+        ) => None,
+        (Architecture::X86_32(3), Vendor::Sun) => Some("4.0.2"),
+        (Architecture::Sparc, _) | (_, Vendor::Sun) => Some("4.1.1"),
+        _ => None,
+    }
+}
+
+///
+/// Expands a legacy machine name, optionally followed by a system (such as `sun4` or `pentium-linux`),
+///  or a target with only an architecture and a manufacturer known to config.sub (such as `sparc-sun`)
+pub fn expand(s: &str) -> Result<CanonicalTarget, UnknownError> {
+    expand_versioned(s).map(|targ| targ.target)
+}
+
+///
+/// Canonicalizes `s` the way GNU config.sub does, additionally accepting the legacy machine names expanded by [`expand`].
+///
+/// This formats the same as [`CanonicalTarget::from_alias`] with [`Canonicalization::GnuCompat`][crate::Canonicalization::GnuCompat],
+///  except that an operating system assumed for a legacy machine keeps the version config.sub gives it, such as `sunos4.1.1` for `sun4`
+pub fn canonicalize_gnu(s: &str) -> Result<GnuTarget, UnknownError> {
+    match s.parse() {
+        Ok(target) => Ok(GnuTarget {
+            target,
+            os_version: None,
+        }),
+        Err(_) => expand_versioned(s),
+    }
+}
+
+///
+/// A target canonicalized by [`canonicalize_gnu`]. Formats the way GNU config.sub prints it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GnuTarget {
+    pub target: CanonicalTarget,
+    /// The version of the operating system, which config.sub only keeps for one it assumed
+    pub os_version: Option<&'static str>,
+}
+
+impl Display for GnuTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        crate::gnu::fmt_gnu(&self.target, self.os_version, f)
+    }
+}
+
+fn expand_versioned(s: &str) -> Result<GnuTarget, UnknownError> {
+    if let Some(machine) = find_machine(s) {
+        let vendor = machine.vendor.unwrap_or(Vendor::Unknown);
+        let (sys, os_version) = match machine.sys {
+            Some(sys) => (sys, machine.os_version),
+            None => (
+                default_system(machine.arch, vendor),
+                default_os_version(machine.arch, vendor),
+            ),
+        };
+
+        return Ok(GnuTarget {
+            target: CanonicalTarget {
+                arch: machine.arch,
+                vendor: machine.vendor,
+                sys,
+            },
+            os_version,
+        });
+    }
+
+    for (idx, _) in s.match_indices('-') {
+        let (name, sys) = (&s[..idx], &s[(idx + 1)..]);
+
+        if let Some(machine) = find_machine(name) {
+            let sys = sys.parse::<System>()?;
+
+            return Ok(GnuTarget {
+                target: CanonicalTarget {
+                    arch: machine.arch,
+                    vendor: machine.vendor,
+                    sys,
+                },
+                os_version: None,
+            });
+        }
+    }
+
+    let (arch, vendor) = s.split_once('-').ok_or(UnknownError)?;
+    let arch = Architecture::from_str(arch)?;
+
+    // Only manufacturers that config.sub does not mistake for an operating system
+    match Vendor::parse(vendor) {
+        vendor @ (Vendor::DEC
+        | Vendor::SGI
+        | Vendor::Sun
+        | Vendor::IBM
+        | Vendor::HP
+        | Vendor::Apple
+        | Vendor::Motorola
+        | Vendor::Commodore) => Ok(GnuTarget {
+            target: CanonicalTarget {
+                arch,
+                vendor: Some(vendor),
+                sys: default_system(arch, vendor),
+            },
+            os_version: default_os_version(arch, vendor),
+        }),
+        _ => Err(UnknownError),
+    }
+}
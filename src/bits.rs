@@ -0,0 +1,156 @@
+//! The packed encoding of a [`CanonicalTarget`] into a [`u64`], used by [`CanonicalTarget::to_bits`] and [`CanonicalTarget::from_bits`]
+//!
+//! Each piece of the target occupies one byte, from least to most significant:
+//!
+//! | Bits   | Piece                                                              |
+//! |--------|--------------------------------------------------------------------|
+//! | 0..8   | The architecture, numbered in the table below                      |
+//! | 8..16  | The microarchitecture level of x86 architectures, and `0` otherwise |
+//! | 16..24 | The vendor                                                         |
+//! | 24..32 | The kernel of the system                                           |
+//! | 32..40 | The operating system                                               |
+//! | 40..48 | The environment                                                    |
+//! | 48..56 | The object format                                                  |
+//! | 56..64 | Reserved, and always `0`                                           |
+//!
+//! The vendor, kernel, operating system, environment, and object format are encoded as `0` if absent,
+//!  and otherwise as one more than the explicit discriminant of the piece (so [`Vendor::WDC`], which is `15`, is encoded as `16`).
+//!
+//! The architectures are numbered by [`Architecture::code`], as follows:
+//!
+//! | Code | Architecture  | Code | Architecture  | Code | Architecture  |
+//! |------|---------------|------|---------------|------|---------------|
+//! | 0    | `Unknown`     | 10   | `MipsLE`      | 20   | `SparcEL`     |
+//! | 1    | `X86_16`      | 11   | `Mips64`      | 21   | `Wasm32`      |
+//! | 2    | `X86_32`      | 12   | `Mips64LE`    | 22   | `Wasm64`      |
+//! | 3    | `X86_64`      | 13   | `PowerPC32`   | 23   | `Wc65c816`    |
+//! | 4    | `Arm`         | 14   | `PowerPC64`   | 24   | `M6502`       |
+//! | 5    | `ArmBe`       | 15   | `PowerPC64le` | 25   | `M65C02`      |
+//! | 6    | `Aarch64`     | 16   | `RiscV32`     | 26   | `SPC700`      |
+//! | 7    | `Aarch64Be`   | 17   | `RiscV64`     | 27   | `Clever`      |
+//! | 8    | `Aarch64_32`  | 18   | `Sparc`       | 28   | `HoleyBytes`  |
+//! | 9    | `Mips`        | 19   | `SparcV9`     | 29   | `M68k`        |
+//!
+//! The encoding is stable: the code of a piece is never changed or reused, and new pieces are given new codes.
+//! Encodings produced by one version of the library are accepted by all later versions.
+//!
+//! The kernel is encoded even when the operating system and environment imply it. On decoding, a kernel is only rejected
+//!  if it runs a different operating system. A kernel that cannot be part of the system, or an absent kernel, decodes
+//!  to the kernel the system implies.
+
+use crate::{pieces::*, CanonicalTarget};
+
+macro_rules! decode_table {
+    ($name:ident: $ty:ident { $($variant:ident),* $(,)? }) => {
+        pub(crate) fn $name(code: u8) -> Result<Option<$ty>, UnknownError> {
+            $(if code == ($ty::$variant as u8) + 1 {
+                return Ok(Some($ty::$variant));
+            })*
+
+            if code == 0 {
+                Ok(None)
+            } else {
+                Err(UnknownError)
+            }
+        }
+    };
+}
+
+decode_table!(decode_vendor: Vendor {
+    Unknown, Apple, PC, SCEI, Freescale, IBM, ImaginationTechnologies, MipsTechnologies, NVIDIA, CSR,
+    Myriad, AMD, Mesa, SUSE, OpenEmbedded, WDC, Sun, DEC, SGI, HP, Motorola, Commodore,
+});
+
+decode_table!(decode_kernel: Kernel {
+    Unknown, Linux, UCLinux, KFreeBSD, KNetBSD, KOpenSolaris, NetBSD, CloudABI, Nto,
+});
+
+decode_table!(decode_os: OS {
+    Unknown, Ananas, CloudABI, Darwin, DragonFly, FreeBSD, Fuchsia, IOS, KFreeBSD, Linux, Lv2, MacOSX,
+    NetBSD, OpenBSD, Solaris, Win32, ZOS, Haiku, Minix, RTEMS, NaCl, AIX, CUDA, NVCL, AMDHSA, PS4,
+    ELFIAMCU, TvOS, WatchOS, Mesa3D, Contiki, AMDPAL, HermitCore, Hurd, WASI, Emscripten, SNES, NES,
+    None, CleverOS, AbleOS, Lilium, QNX, KNetBSD, KOpenSolaris, SunOS, Ultrix, AmigaOS, IRIX, HPUX,
+    SysV,
+});
+
+decode_table!(decode_env: Environment {
+    Unknown, GNU, GNUABIN32, GNUABI64, GNUEABI, GNUEABIHF, GNUX32, CODE16, EABI, EABIHF, Android, Musl,
+    MuslEABI, MuslEABIHF, MSVC, Itanium, Cygnus, CoreCLR, Simulator, MacABI, Standard, Kernel, UClibc,
+});
+
+decode_table!(decode_objfmt: ObjectFormat {
+    Unknown, XCoff, Coff, Elf, Goff, MachO, Wasm, Xo65, O65, WlaObj,
+});
+
+pub(crate) fn decode_arch(code: u8, level: u8) -> Result<Architecture, UnknownError> {
+    Architecture::from_code(code, level).ok_or(UnknownError)
+}
+
+const KERNEL_MASK: u64 = 0xff << 24;
+
+macro_rules! encode_piece {
+    ($piece:expr) => {
+        match $piece {
+            Some(piece) => piece as u64 + 1,
+            None => 0,
+        }
+    };
+}
+
+impl CanonicalTarget {
+    ///
+    /// Packs the target into a [`u64`]. The layout of the encoding is documented in the [`bits`][crate::bits] module.
+    ///
+    /// The encoding is stable across versions of the library, and [`CanonicalTarget::from_bits`] returns the same target
+    pub const fn to_bits(&self) -> u64 {
+        let (arch, level) = self.arch.code();
+
+        (arch as u64)
+            | (level as u64) << 8
+            | encode_piece!(self.vendor) << 16
+            | encode_piece!(self.sys.kernel()) << 24
+            | encode_piece!(self.sys.os()) << 32
+            | encode_piece!(self.sys.env()) << 40
+            | encode_piece!(self.sys.object_format()) << 48
+    }
+
+    ///
+    /// Unpacks a target produced by [`CanonicalTarget::to_bits`].
+    ///
+    /// Returns an error if `bits` is not the encoding of any target, including if it was produced by a later version of the library
+    ///  that knows of pieces this version does not
+    pub fn from_bits(bits: u64) -> Result<Self, UnknownError> {
+        let byte = |n: u32| (bits >> (n * 8)) as u8;
+
+        let arch = decode_arch(byte(0), byte(1))?;
+        let vendor = decode_vendor(byte(2))?;
+        let kernel = decode_kernel(byte(3))?;
+        let os = decode_os(byte(4))?;
+        let env = decode_env(byte(5))?;
+        let objfmt = decode_objfmt(byte(6))?;
+
+        // The kernel is kept if it fits the rest of the system, even if it is not the one the system implies.
+        //  Otherwise the system implies its kernel, so that encodings stay valid if the inferred kernels change
+        let sys = match kernel {
+            Some(kernel) if os != Some(kernel.os()) => return Err(UnknownError),
+            Some(kernel) if objfmt.is_none() && kernel.accepts_env(env) => {
+                System::from_kernel(kernel, env)
+            }
+            _ => {
+                if os.is_none() && env.is_none() && objfmt.is_none() {
+                    return Err(UnknownError);
+                }
+                System::from_pieces(os, env, objfmt)
+            }
+        };
+
+        let targ = CanonicalTarget { arch, vendor, sys };
+
+        // Reject encodings of pieces that do not fit together, such as a microarchitecture on a non-x86 architecture
+        if targ.to_bits() & !KERNEL_MASK == bits & !KERNEL_MASK {
+            Ok(targ)
+        } else {
+            Err(UnknownError)
+        }
+    }
+}
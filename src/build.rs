@@ -0,0 +1,258 @@
+//! Helpers for build scripts
+//!
+//! Cargo passes the target being compiled for to build scripts both as a tuple, in the `TARGET` environment variable,
+//!  and as the set of cfgs rustc defines for it, in the `CARGO_CFG_TARGET_*` environment variables.
+//! This module reads both, reports where they disagree, and emits cfgs derived from the tuple that rustc does not provide.
+
+use core::fmt::{Display, Formatter};
+use std::env;
+use std::string::String;
+use std::vec::Vec;
+
+use crate::{cfg::TargetCfg, pieces::*, CanonicalTarget, OwnedTarget};
+
+fn read_target(var: &str) -> Result<OwnedTarget, UnknownError> {
+    let name = env::var(var).map_err(|_| UnknownError)?;
+
+    OwnedTarget::from_owned(name)
+}
+
+/// Reads the target being compiled for from the `TARGET` environment variable.
+/// Returns an error if the variable is not set, or if the target is not known to the library
+pub fn target() -> Result<OwnedTarget, UnknownError> {
+    read_target("TARGET")
+}
+
+/// Reads the target the build script is running on from the `HOST` environment variable.
+/// Returns an error if the variable is not set, or if the target is not known to the library
+pub fn host() -> Result<OwnedTarget, UnknownError> {
+    read_target("HOST")
+}
+
+///
+/// The cfgs of the target being compiled for, as given to build scripts by cargo in the `CARGO_CFG_TARGET_*` environment variables.
+///
+/// Keys that cargo does not set are represented by an empty string
+#[derive(Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct CargoCfg {
+    /// The value of `CARGO_CFG_TARGET_ARCH`
+    pub arch: String,
+    /// The value of `CARGO_CFG_TARGET_VENDOR`
+    pub vendor: String,
+    /// The value of `CARGO_CFG_TARGET_OS`
+    pub os: String,
+    /// The value of `CARGO_CFG_TARGET_ENV`
+    pub env: String,
+    /// The value of `CARGO_CFG_TARGET_ABI`
+    pub abi: String,
+    /// The value of `CARGO_CFG_TARGET_ENDIAN`
+    pub endian: String,
+    /// The value of `CARGO_CFG_TARGET_POINTER_WIDTH`
+    pub pointer_width: String,
+    /// The comma-separated values of `CARGO_CFG_TARGET_FEATURE`
+    pub features: Vec<String>,
+}
+
+impl CargoCfg {
+    /// Reads the cfgs from the environment of the build script
+    pub fn from_env() -> Self {
+        let var = |name: &str| env::var(name).unwrap_or_default();
+
+        Self {
+            arch: var("CARGO_CFG_TARGET_ARCH"),
+            vendor: var("CARGO_CFG_TARGET_VENDOR"),
+            os: var("CARGO_CFG_TARGET_OS"),
+            env: var("CARGO_CFG_TARGET_ENV"),
+            abi: var("CARGO_CFG_TARGET_ABI"),
+            endian: var("CARGO_CFG_TARGET_ENDIAN"),
+            pointer_width: var("CARGO_CFG_TARGET_POINTER_WIDTH"),
+            features: var("CARGO_CFG_TARGET_FEATURE")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// Converts the cfgs into a target. See [`TargetCfg::to_target`]
+    pub fn to_target(&self) -> CanonicalTarget {
+        let features = self.features.iter().map(|f| &**f).collect::<Vec<_>>();
+
+        TargetCfg {
+            arch: &self.arch,
+            vendor: &self.vendor,
+            os: &self.os,
+            env: &self.env,
+            abi: &self.abi,
+            endian: &self.endian,
+            pointer_width: &self.pointer_width,
+            features: &features,
+        }
+        .to_target()
+    }
+}
+
+///
+/// A piece of a target tuple that disagrees with the cfgs cargo provides for the same target
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Mismatch {
+    /// The architecture of the tuple is not described by the cfgs
+    Arch {
+        tuple: Architecture,
+        cfg: Architecture,
+    },
+    /// The vendor of the tuple differs from `target_vendor`
+    Vendor { tuple: Vendor, cfg: Vendor },
+    /// The operating system or environment of the tuple differs from `target_os`, `target_env`, or `target_abi`
+    System { tuple: System, cfg: System },
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Mismatch::Arch { tuple, cfg } => f.write_fmt(format_args!(
+                "the target has architecture {}, but the cfgs describe {}",
+                tuple, cfg
+            )),
+            Mismatch::Vendor { tuple, cfg } => f.write_fmt(format_args!(
+                "the target has vendor {}, but the cfgs describe {}",
+                tuple, cfg
+            )),
+            Mismatch::System { tuple, cfg } => f.write_fmt(format_args!(
+                "the target has system {}, but the cfgs describe {}",
+                tuple, cfg
+            )),
+        }
+    }
+}
+
+/// Checks that the cfgs support the architecture of the tuple. The cfgs may enable a newer x86 microarchitecture than the tuple names,
+///  as with `-C target-cpu`
+fn arch_matches(tuple: Architecture, cfg: Architecture) -> bool {
+    match (tuple, cfg) {
+        (Architecture::X86_16(tuple), Architecture::X86_16(cfg))
+        | (Architecture::X86_32(tuple), Architecture::X86_32(cfg))
+        | (Architecture::X86_64 { microarch: tuple }, Architecture::X86_64 { microarch: cfg }) => {
+            tuple <= cfg
+        }
+        (tuple, cfg) => tuple == cfg,
+    }
+}
+
+///
+/// Compares `targ` with the target described by `cfg`.
+///
+/// Pieces that the tuple leaves unspecified, such as the vendor of `x86_64-linux-android`, are not compared
+pub fn mismatches(targ: &CanonicalTarget, cfg: &CargoCfg) -> Vec<Mismatch> {
+    let from_cfg = cfg.to_target();
+    let mut mismatches = Vec::new();
+
+    if !arch_matches(targ.arch, from_cfg.arch) {
+        mismatches.push(Mismatch::Arch {
+            tuple: targ.arch,
+            cfg: from_cfg.arch,
+        });
+    }
+
+    if let (Some(tuple), Some(cfg)) = (targ.vendor, from_cfg.vendor) {
+        if tuple != cfg {
+            mismatches.push(Mismatch::Vendor { tuple, cfg });
+        }
+    }
+
+    let env_matches = targ.sys.env().is_none() || targ.sys.env() == from_cfg.sys.env();
+    if targ.sys.os() != from_cfg.sys.os() || !env_matches {
+        mismatches.push(Mismatch::System {
+            tuple: targ.sys,
+            cfg: from_cfg.sys,
+        });
+    }
+
+    mismatches
+}
+
+/// Reads `TARGET` and the `CARGO_CFG_TARGET_*` variables, and emits a `cargo:warning` for each piece of the tuple the cfgs disagree with.
+/// Returns an error if `TARGET` cannot be read
+pub fn report_mismatches() -> Result<(), UnknownError> {
+    let targ = target()?;
+
+    for mismatch in mismatches(&targ, &CargoCfg::from_env()) {
+        std::println!("cargo:warning={}: {}", targ.borrow().exact(), mismatch);
+    }
+
+    Ok(())
+}
+
+const FAMILIES: &[&str] = &[
+    "unknown",
+    "x86",
+    "arm",
+    "aarch64",
+    "mips",
+    "powerpc",
+    "riscv",
+    "sparc",
+    "wasm",
+    "6502",
+    "spc700",
+    "clever",
+    "holeybytes",
+    "m68k",
+];
+
+const OBJECT_FORMATS: &[&str] = &[
+    "unknown", "xcoff", "coff", "elf", "goff", "macho", "wasm", "xo65", "o65", "wlaobj",
+];
+
+const MICROARCHES: &[&str] = &["0", "1", "2", "3", "4", "5", "6", "7"];
+
+/// The custom cfgs emitted by [`emit_cfgs`], with every value they can take
+pub const CUSTOM_CFGS: &[(&str, &[&str])] = &[
+    ("tuple_family", FAMILIES),
+    ("tuple_objfmt", OBJECT_FORMATS),
+    ("tuple_microarch", MICROARCHES),
+];
+
+///
+/// Returns the custom cfgs derived from `targ`, as name-value pairs:
+/// * `tuple_family`: the family of the architecture, see [`Architecture::family`]
+/// * `tuple_objfmt`: the object format, see [`CanonicalTarget::object_format`]
+/// * `tuple_microarch`: the microarchitecture level of x86 targets, such as `3` for `x86_64v3` or `6` for `i686`
+pub fn custom_cfgs(targ: &CanonicalTarget) -> Vec<(&'static str, &'static str)> {
+    let mut cfgs = Vec::new();
+
+    cfgs.push(("tuple_family", targ.arch.family()));
+    cfgs.push(("tuple_objfmt", targ.object_format().canonical_name()));
+
+    match targ.arch {
+        Architecture::X86_16(level)
+        | Architecture::X86_32(level)
+        | Architecture::X86_64 { microarch: level } => {
+            cfgs.push(("tuple_microarch", MICROARCHES[(level as usize).min(7)]));
+        }
+        _ => {}
+    }
+
+    cfgs
+}
+
+/// Emits `cargo::rustc-check-cfg` lines declaring the custom cfgs, and `cargo:rustc-cfg` lines setting them for `targ`.
+/// See [`custom_cfgs`]
+pub fn emit_cfgs(targ: &CanonicalTarget) {
+    for (name, values) in CUSTOM_CFGS {
+        let values = values
+            .iter()
+            .map(|value| std::format!("{:?}", value))
+            .collect::<Vec<_>>();
+        std::println!(
+            "cargo::rustc-check-cfg=cfg({}, values({}))",
+            name,
+            values.join(", ")
+        );
+    }
+
+    for (name, value) in custom_cfgs(targ) {
+        std::println!("cargo:rustc-cfg={}=\"{}\"", name, value);
+    }
+}
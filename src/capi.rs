@@ -0,0 +1,335 @@
+//! A C interface to the library, enabled by the `capi` feature
+//!
+//! Targets are passed to and from C as a [`TargetTuple`], which holds the code of each piece of the target.
+//! The codes are the same as those of the packed encoding, documented in the [`bits`][crate::bits] module.
+//!
+//! The `target-tuples-capi` crate builds this interface as a shared and static library, and ships the C header
+//!  produced by [`header`].
+
+#![allow(unsafe_code)]
+
+#[cfg(feature = "alloc")]
+use alloc::{format, string::String};
+use core::ffi::{c_char, c_int};
+use core::fmt::Write;
+
+use crate::{pieces::*, CanonicalTarget};
+
+///
+/// A target, as the codes of its pieces. Corresponds to `struct target_tuple` in C.
+///
+/// A value that does not describe a target, such as one with an unknown code, is rejected by each function that accepts one
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct TargetTuple {
+    /// The architecture
+    pub arch: u8,
+    /// The microarchitecture level of x86 architectures, and `0` otherwise
+    pub microarch: u8,
+    /// The vendor, or `0` if the target does not name one
+    pub vendor: u8,
+    /// The kernel, or `0` if the system has none
+    pub kernel: u8,
+    /// The operating system, or `0` if the system has none
+    pub os: u8,
+    /// The environment, or `0` if the system has none
+    pub env: u8,
+    /// The object format, or `0` if the system does not name one
+    pub objfmt: u8,
+}
+
+impl From<CanonicalTarget> for TargetTuple {
+    fn from(targ: CanonicalTarget) -> Self {
+        let [arch, microarch, vendor, kernel, os, env, objfmt, _] = targ.to_bits().to_le_bytes();
+
+        Self {
+            arch,
+            microarch,
+            vendor,
+            kernel,
+            os,
+            env,
+            objfmt,
+        }
+    }
+}
+
+impl TryFrom<TargetTuple> for CanonicalTarget {
+    type Error = UnknownError;
+
+    fn try_from(targ: TargetTuple) -> Result<Self, UnknownError> {
+        CanonicalTarget::from_bits(u64::from_le_bytes([
+            targ.arch,
+            targ.microarch,
+            targ.vendor,
+            targ.kernel,
+            targ.os,
+            targ.env,
+            targ.objfmt,
+            0,
+        ]))
+    }
+}
+
+///
+/// A string which is not nul-terminated, with static lifetime. Corresponds to `struct target_tuple_name` in C.
+///
+/// A missing name is represented by a null pointer and a length of `0`
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct TargetTupleName {
+    /// The bytes of the name
+    pub name: *const c_char,
+    /// The length of the name in bytes
+    pub len: usize,
+}
+
+impl TargetTupleName {
+    const NONE: Self = Self {
+        name: core::ptr::null(),
+        len: 0,
+    };
+
+    fn new(name: &'static str) -> Self {
+        Self {
+            name: name.as_ptr().cast(),
+            len: name.len(),
+        }
+    }
+}
+
+/// Reads the target pointed to by `targ`.
+///
+/// ## Safety
+/// `targ` must be null, or valid for reads
+unsafe fn read_target(targ: *const TargetTuple) -> Option<CanonicalTarget> {
+    // SAFETY: Guaranteed by the caller
+    let targ = unsafe { targ.as_ref() }?;
+
+    CanonicalTarget::try_from(*targ).ok()
+}
+
+///
+/// Parses the `len` bytes at `name` as a target, and stores the result in `out`.
+///
+/// Returns `0` on success, and `-1` if the name is not a target known to the library, or if either pointer is null.
+///
+/// ## Safety
+/// `name` must be valid for reads of `len` bytes, and `out` must be null or valid for writes
+#[no_mangle]
+pub unsafe extern "C" fn target_tuple_parse(
+    name: *const c_char,
+    len: usize,
+    out: *mut TargetTuple,
+) -> c_int {
+    if name.is_null() || out.is_null() {
+        return -1;
+    }
+
+    // SAFETY: Guaranteed by the caller
+    let name = unsafe { core::slice::from_raw_parts(name.cast::<u8>(), len) };
+
+    let Ok(targ) = core::str::from_utf8(name)
+        .map_err(|_| UnknownError)
+        .and_then(str::parse::<CanonicalTarget>)
+    else {
+        return -1;
+    };
+
+    // SAFETY: Guaranteed by the caller
+    unsafe { out.write(targ.into()) };
+
+    0
+}
+
+/// Writes as much of a string as fits into a buffer, leaving room for a nul terminator
+struct TruncatingWriter<'a> {
+    buf: &'a mut [u8],
+    written: usize,
+}
+
+impl Write for TruncatingWriter<'_> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        let start = self.written.min(self.buf.len());
+        let room = self.buf.len().saturating_sub(start + 1);
+        let n = room.min(s.len());
+
+        self.buf[start..(start + n)].copy_from_slice(&s.as_bytes()[..n]);
+        self.written += s.len();
+
+        Ok(())
+    }
+}
+
+///
+/// Writes the canonical name of `targ` to `buf`, in the manner of `snprintf`.
+///
+/// At most `len` bytes are written, including the nul terminator, which is always written if `len` is not `0`.
+/// Returns the length of the full name, excluding the nul terminator, or `SIZE_MAX` if `targ` does not describe a target.
+///
+/// ## Safety
+/// `targ` must be null or valid for reads, and `buf` must be valid for writes of `len` bytes
+#[no_mangle]
+pub unsafe extern "C" fn target_tuple_format(
+    targ: *const TargetTuple,
+    buf: *mut c_char,
+    len: usize,
+) -> usize {
+    // SAFETY: Guaranteed by the caller
+    let Some(targ) = (unsafe { read_target(targ) }) else {
+        return usize::MAX;
+    };
+
+    let buf: &mut [u8] = if len == 0 {
+        &mut []
+    } else {
+        // SAFETY: Guaranteed by the caller
+        unsafe { core::slice::from_raw_parts_mut(buf.cast::<u8>(), len) }
+    };
+
+    let mut writer = TruncatingWriter { buf, written: 0 };
+    // Writing to a `TruncatingWriter` never fails
+    let _ = write!(writer, "{}", targ);
+
+    let end = writer.written.min(len.saturating_sub(1));
+    if let Some(nul) = writer.buf.get_mut(end) {
+        *nul = 0;
+    }
+
+    writer.written
+}
+
+macro_rules! name_fns {
+    ($($(#[$meta:meta])* $name:ident => |$targ:ident| $piece:expr;)*) => {
+        $(
+            $(#[$meta])*
+            ///
+            /// ## Safety
+            /// `targ` must be null or valid for reads
+            #[no_mangle]
+            pub unsafe extern "C" fn $name(targ: *const TargetTuple) -> TargetTupleName {
+                // SAFETY: Guaranteed by the caller
+                match unsafe { read_target(targ) } {
+                    Some($targ) => $piece,
+                    None => TargetTupleName::NONE,
+                }
+            }
+        )*
+    };
+}
+
+name_fns! {
+    /// Returns the canonical name of the architecture of `targ`. Returns a null name if `targ` does not describe a target
+    target_tuple_arch_name => |targ| TargetTupleName::new(targ.arch.canonical_name());
+    /// Returns the canonical name of the vendor of `targ`, which is guessed if the target does not name one.
+    /// Returns a null name if `targ` does not describe a target
+    target_tuple_vendor_name => |targ| TargetTupleName::new(targ.guess_vendor().canonical_name());
+    /// Returns the canonical name of the operating system of `targ`.
+    /// Returns a null name if the system has none, or if `targ` does not describe a target
+    target_tuple_os_name => |targ| targ.sys.os().map_or(TargetTupleName::NONE, |os| TargetTupleName::new(os.canonical_name()));
+    /// Returns the canonical name of the environment of `targ`.
+    /// Returns a null name if the system has none, or if `targ` does not describe a target
+    target_tuple_env_name => |targ| targ.sys.env().map_or(TargetTupleName::NONE, |env| TargetTupleName::new(env.canonical_name()));
+    /// Returns the canonical name of the object format of `targ`, which is the default for the system if it does not name one.
+    /// Returns a null name if `targ` does not describe a target
+    target_tuple_objfmt_name => |targ| TargetTupleName::new(targ.object_format().canonical_name());
+}
+
+#[cfg(feature = "alloc")]
+const HEADER_PRELUDE: &str = "\
+/* Generated by target_tuples::capi::header. Do not edit. */
+
+#ifndef TARGET_TUPLES_H
+#define TARGET_TUPLES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern \"C\" {
+#endif
+
+struct target_tuple {
+    uint8_t arch;
+    uint8_t microarch;
+    uint8_t vendor;
+    uint8_t kernel;
+    uint8_t os;
+    uint8_t env;
+    uint8_t objfmt;
+};
+
+struct target_tuple_name {
+    const char *name;
+    size_t len;
+};
+
+int target_tuple_parse(const char *name, size_t len, struct target_tuple *out);
+size_t target_tuple_format(const struct target_tuple *targ, char *buf, size_t len);
+
+struct target_tuple_name target_tuple_arch_name(const struct target_tuple *targ);
+struct target_tuple_name target_tuple_vendor_name(const struct target_tuple *targ);
+struct target_tuple_name target_tuple_os_name(const struct target_tuple *targ);
+struct target_tuple_name target_tuple_env_name(const struct target_tuple *targ);
+struct target_tuple_name target_tuple_objfmt_name(const struct target_tuple *targ);
+";
+
+#[cfg(feature = "alloc")]
+const HEADER_EPILOGUE: &str = "
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TARGET_TUPLES_H */
+";
+
+///
+/// Returns the C header declaring the interface, along with a macro for the code of each piece,
+///  such as `TARGET_TUPLE_ARCH_X86_64` or `TARGET_TUPLE_VENDOR_PC`
+#[cfg(feature = "alloc")]
+pub fn header() -> String {
+    fn define<T: core::fmt::Debug>(out: &mut String, prefix: &str, code: u8, piece: T) {
+        let name = format!("{:?}", piece);
+        // Strip the microarchitecture of x86 architectures
+        let name = name.split(['(', ' ']).next().unwrap_or(&name);
+
+        out.push_str(&format!(
+            "#define TARGET_TUPLE_{}_{} {}\n",
+            prefix,
+            name.to_ascii_uppercase(),
+            code
+        ));
+    }
+
+    fn defines<T: core::fmt::Debug>(
+        out: &mut String,
+        prefix: &str,
+        decode: fn(u8) -> Result<Option<T>, UnknownError>,
+    ) {
+        out.push('\n');
+        for code in 1..=u8::MAX {
+            if let Ok(Some(piece)) = decode(code) {
+                define(out, prefix, code, piece);
+            }
+        }
+    }
+
+    let mut out = String::from(HEADER_PRELUDE);
+
+    out.push('\n');
+    for code in 0..=u8::MAX {
+        if let Ok(arch) = crate::bits::decode_arch(code, 0) {
+            define(&mut out, "ARCH", code, arch);
+        }
+    }
+
+    defines(&mut out, "VENDOR", crate::bits::decode_vendor);
+    defines(&mut out, "KERNEL", crate::bits::decode_kernel);
+    defines(&mut out, "OS", crate::bits::decode_os);
+    defines(&mut out, "ENV", crate::bits::decode_env);
+    defines(&mut out, "OBJFMT", crate::bits::decode_objfmt);
+
+    out.push_str(HEADER_EPILOGUE);
+
+    out
+}
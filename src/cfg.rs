@@ -0,0 +1,909 @@
+//! Conversions between targets and the `target_*` cfgs defined by rustc
+//!
+//! rustc does not use target tuples in its cfgs. Instead, it describes a target by a set of key-value pairs,
+//!  such as `target_arch = "x86_64"` and `target_os = "linux"`.
+
+use core::fmt::{Display, Formatter};
+
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    pieces::{rustc, *},
+    CanonicalTarget,
+};
+
+///
+/// The values of the cfgs that rustc defines for a target.
+///
+/// A key which rustc does not set for a target (such as `target_env` on `x86_64-apple-darwin`) is represented by an empty string
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq)]
+pub struct TargetCfg<'a> {
+    /// The value of `target_arch`
+    pub arch: &'a str,
+    /// The value of `target_vendor`
+    pub vendor: &'a str,
+    /// The value of `target_os`
+    pub os: &'a str,
+    /// The value of `target_env`
+    pub env: &'a str,
+    /// The value of `target_abi`
+    pub abi: &'a str,
+    /// The value of `target_endian`
+    pub endian: &'a str,
+    /// The value of `target_pointer_width`
+    pub pointer_width: &'a str,
+    /// The enabled values of `target_feature`. Only features that affect the architecture are consulted,
+    ///  such as `sse2` on x86 or `avx2` on x86_64
+    pub features: &'a [&'a str],
+}
+
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+macro_rules! select_cfg {
+    ($key:ident: $($val:literal),* $(,)?) => {
+        $(if cfg!($key = $val) { $val } else)* { "" }
+    };
+}
+
+/// The value of `target_abi` for the target this crate is compiled for
+#[cfg(has_target_abi)]
+const CURRENT_ABI: &str =
+    select_cfg!(target_abi: "eabi", "eabihf", "x32", "abi64", "ilp32", "sim", "macabi");
+
+/// `cfg(target_abi)` cannot be checked before Rust 1.78
+#[cfg(not(has_target_abi))]
+const CURRENT_ABI: &str = "";
+
+impl TargetCfg<'static> {
+    ///
+    /// The cfgs of the target this crate is compiled for.
+    ///
+    /// `target_abi` is only known when the crate is compiled by Rust 1.78 or later, and is otherwise empty.
+    /// So [`CanonicalTarget::CURRENT`] has no ABI in its environment on older compilers, such as `arm-unknown-linux-gnu` rather than `arm-unknown-linux-gnueabihf`
+    #[allow(unknown_lints, unexpected_cfgs)]
+    pub const CURRENT: TargetCfg<'static> = TargetCfg {
+        arch: select_cfg!(target_arch: "x86_64", "x86", "arm", "aarch64", "mips", "mips64", "powerpc", "powerpc64", "riscv32", "riscv64", "sparc", "sparc64", "wasm32", "wasm64", "m68k"),
+        vendor: select_cfg!(target_vendor: "unknown", "pc", "apple", "nvidia", "sun", "ibm", "amd", "mti"),
+        os: select_cfg!(target_os: "linux", "android", "macos", "ios", "tvos", "watchos", "windows", "freebsd", "netbsd", "openbsd", "dragonfly", "fuchsia", "haiku", "solaris", "illumos", "hermit", "wasi", "emscripten", "cuda", "amdhsa", "aix", "hurd", "nto", "rtems", "none", "unknown"),
+        env: select_cfg!(target_env: "gnu", "musl", "uclibc", "msvc", "sim", "macabi"),
+        abi: CURRENT_ABI,
+        endian: select_cfg!(target_endian: "little", "big"),
+        pointer_width: select_cfg!(target_pointer_width: "16", "32", "64"),
+        features: &[
+            select_cfg!(target_feature: "sse2"),
+            select_cfg!(target_feature: "sse4.2"),
+            select_cfg!(target_feature: "popcnt"),
+            select_cfg!(target_feature: "avx2"),
+            select_cfg!(target_feature: "avx512f"),
+        ],
+    };
+}
+
+impl<'a> TargetCfg<'a> {
+    /// Checks whether `feature` is among the enabled target features
+    pub const fn has_feature(&self, feature: &str) -> bool {
+        let mut i = 0;
+        while i < self.features.len() {
+            if str_eq(self.features[i], feature) {
+                return true;
+            }
+            i += 1;
+        }
+        false
+    }
+
+    const fn to_arch(self) -> Architecture {
+        let little = str_eq(self.endian, "little");
+        let big = str_eq(self.endian, "big");
+        let arch = self.arch;
+
+        if str_eq(arch, "x86_64") {
+            let microarch = if self.has_feature("avx512f") {
+                4
+            } else if self.has_feature("avx2") {
+                3
+            } else if self.has_feature("sse4.2") && self.has_feature("popcnt") {
+                2
+            } else {
+                1
+            };
+            Architecture::X86_64 { microarch }
+        } else if str_eq(arch, "x86") {
+            if self.has_feature("sse2") {
+                Architecture::X86_32(6)
+            } else {
+                Architecture::X86_32(5)
+            }
+        } else if str_eq(arch, "arm") {
+            if big {
+                Architecture::ArmBe
+            } else {
+                Architecture::Arm
+            }
+        } else if str_eq(arch, "aarch64") {
+            if big {
+                Architecture::Aarch64Be
+            } else if str_eq(self.pointer_width, "32") && !str_eq(self.abi, "ilp32") {
+                // `ilp32` is the 32-bit ABI of AArch64 Linux, not the `arm64_32` architecture
+                Architecture::Aarch64_32
+            } else {
+                Architecture::Aarch64
+            }
+        } else if str_eq(arch, "mips") || str_eq(arch, "mips32r6") {
+            if little {
+                Architecture::MipsLE
+            } else {
+                Architecture::Mips
+            }
+        } else if str_eq(arch, "mips64") || str_eq(arch, "mips64r6") {
+            if little {
+                Architecture::Mips64LE
+            } else {
+                Architecture::Mips64
+            }
+        } else if str_eq(arch, "powerpc") {
+            Architecture::PowerPC32
+        } else if str_eq(arch, "powerpc64") {
+            if little {
+                Architecture::PowerPC64le
+            } else {
+                Architecture::PowerPC64
+            }
+        } else if str_eq(arch, "riscv32") {
+            Architecture::RiscV32
+        } else if str_eq(arch, "riscv64") {
+            Architecture::RiscV64
+        } else if str_eq(arch, "sparc") {
+            Architecture::Sparc
+        } else if str_eq(arch, "sparc64") {
+            Architecture::SparcV9
+        } else if str_eq(arch, "wasm32") {
+            Architecture::Wasm32
+        } else if str_eq(arch, "wasm64") {
+            Architecture::Wasm64
+        } else if str_eq(arch, "m68k") {
+            Architecture::M68k
+        } else {
+            Architecture::Unknown
+        }
+    }
+
+    const fn to_vendor(self) -> Vendor {
+        let vendor = self.vendor;
+
+        if str_eq(vendor, "pc") {
+            Vendor::PC
+        } else if str_eq(vendor, "apple") {
+            Vendor::Apple
+        } else if str_eq(vendor, "nvidia") {
+            Vendor::NVIDIA
+        } else if str_eq(vendor, "sun") {
+            Vendor::Sun
+        } else if str_eq(vendor, "ibm") {
+            Vendor::IBM
+        } else if str_eq(vendor, "amd") {
+            Vendor::AMD
+        } else if str_eq(vendor, "mti") {
+            Vendor::MipsTechnologies
+        } else {
+            Vendor::Unknown
+        }
+    }
+
+    const fn to_os(self) -> Option<OS> {
+        let os = self.os;
+
+        Some(if str_eq(os, "linux") || str_eq(os, "android") {
+            OS::Linux
+        } else if str_eq(os, "macos") {
+            OS::Darwin
+        } else if str_eq(os, "ios") {
+            OS::IOS
+        } else if str_eq(os, "tvos") {
+            OS::TvOS
+        } else if str_eq(os, "watchos") {
+            OS::WatchOS
+        } else if str_eq(os, "windows") {
+            OS::Win32
+        } else if str_eq(os, "freebsd") {
+            OS::FreeBSD
+        } else if str_eq(os, "netbsd") {
+            OS::NetBSD
+        } else if str_eq(os, "openbsd") {
+            OS::OpenBSD
+        } else if str_eq(os, "dragonfly") {
+            OS::DragonFly
+        } else if str_eq(os, "fuchsia") {
+            OS::Fuchsia
+        } else if str_eq(os, "haiku") {
+            OS::Haiku
+        } else if str_eq(os, "solaris") || str_eq(os, "illumos") {
+            OS::Solaris
+        } else if str_eq(os, "hermit") {
+            OS::HermitCore
+        } else if str_eq(os, "wasi") {
+            OS::WASI
+        } else if str_eq(os, "emscripten") {
+            OS::Emscripten
+        } else if str_eq(os, "cuda") {
+            OS::CUDA
+        } else if str_eq(os, "amdhsa") {
+            OS::AMDHSA
+        } else if str_eq(os, "aix") {
+            OS::AIX
+        } else if str_eq(os, "hurd") {
+            OS::Hurd
+        } else if str_eq(os, "nto") {
+            OS::QNX
+        } else if str_eq(os, "rtems") {
+            OS::RTEMS
+        } else if str_eq(os, "none") {
+            OS::None
+        } else if str_eq(os, "") {
+            return None;
+        } else {
+            OS::Unknown
+        })
+    }
+
+    const fn to_env(self) -> Option<Environment> {
+        let (env, abi) = (self.env, self.abi);
+
+        let eabi = str_eq(abi, "eabi");
+        let eabihf = str_eq(abi, "eabihf");
+
+        Some(if str_eq(env, "gnu") {
+            if eabihf {
+                Environment::GNUEABIHF
+            } else if eabi {
+                Environment::GNUEABI
+            } else if str_eq(abi, "x32") {
+                Environment::GNUX32
+            } else if str_eq(abi, "abi64") {
+                Environment::GNUABI64
+            } else {
+                Environment::GNU
+            }
+        } else if str_eq(env, "musl") {
+            if eabihf {
+                Environment::MuslEABIHF
+            } else if eabi {
+                Environment::MuslEABI
+            } else {
+                Environment::Musl
+            }
+        } else if str_eq(env, "uclibc") {
+            Environment::UClibc
+        } else if str_eq(env, "msvc") {
+            Environment::MSVC
+        } else if str_eq(env, "sim") {
+            Environment::Simulator
+        } else if str_eq(env, "macabi") {
+            Environment::MacABI
+        } else if str_eq(self.os, "android") {
+            Environment::Android
+        } else if eabihf {
+            Environment::EABIHF
+        } else if eabi {
+            Environment::EABI
+        } else {
+            return None;
+        })
+    }
+
+    ///
+    /// Converts the cfgs into the target rustc describes with them.
+    ///
+    /// The conversion is lossy: values unknown to the library become the `Unknown` value of the corresponding piece
+    pub const fn to_target(self) -> CanonicalTarget {
+        let os = self.to_os();
+        let env = self.to_env();
+
+        let sys = if os.is_none() && env.is_none() {
+            System::from_os(OS::Unknown)
+        } else {
+            System::from_pieces(os, env, None)
+        };
+
+        CanonicalTarget::new(self.to_arch(), Some(self.to_vendor()), sys)
+    }
+}
+
+impl CanonicalTarget {
+    /// The target this crate is compiled for, as determined from the cfgs rustc defines.
+    /// See [`TargetCfg::CURRENT`]
+    pub const CURRENT: CanonicalTarget = TargetCfg::CURRENT.to_target();
+}
+
+///
+/// The full set of cfgs rustc defines for a target, as returned by [`CanonicalTarget::rustc_cfgs`]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct RustcCfgs {
+    /// The `target_*` cfgs that have a single value, and the baseline target features
+    pub target: TargetCfg<'static>,
+    /// The values of `target_family`. The `unix` and `windows` families are also defined as bare cfgs
+    pub families: &'static [&'static str],
+    /// The values of `target_has_atomic`
+    pub has_atomic: &'static [&'static str],
+}
+
+impl RustcCfgs {
+    ///
+    /// Iterates over the cfgs as key-value pairs, in the form printed by `rustc --print cfg`.
+    ///
+    /// Bare cfgs, such as `unix`, have no value. Keys with multiple values, such as `target_family`, appear once per value
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, Option<&'static str>)> + '_ {
+        let target = self.target;
+        let single = [
+            ("target_arch", target.arch),
+            ("target_vendor", target.vendor),
+            ("target_os", target.os),
+            ("target_env", target.env),
+            ("target_abi", target.abi),
+            ("target_endian", target.endian),
+            ("target_pointer_width", target.pointer_width),
+        ];
+
+        let bare = self
+            .families
+            .iter()
+            .copied()
+            .filter(|family| *family == "unix" || *family == "windows");
+
+        single
+            .into_iter()
+            .map(|(key, value)| (key, Some(value)))
+            .chain(
+                self.families
+                    .iter()
+                    .map(|family| ("target_family", Some(*family))),
+            )
+            .chain(
+                target
+                    .features
+                    .iter()
+                    .map(|feature| ("target_feature", Some(*feature))),
+            )
+            .chain(
+                self.has_atomic
+                    .iter()
+                    .map(|width| ("target_has_atomic", Some(*width))),
+            )
+            .chain(bare.map(|name| (name, None)))
+    }
+
+    /// Checks whether the cfg `key` (or `key = "value"`, if `value` is given) is defined
+    pub fn contains(&self, key: &str, value: Option<&str>) -> bool {
+        self.iter().any(|cfg| cfg == (key, value))
+    }
+}
+
+/// The widest atomic operation supported by the architecture, in bits
+fn max_atomic_width(targ: &CanonicalTarget) -> u32 {
+    match (targ.arch, targ.sys.os(), targ.sys.env()) {
+        // cmpxchg16b is part of x86-64-v2, and is assumed by the Apple and Windows targets
+        (Architecture::X86_64 { microarch: 2.. }, _, _)
+        | (
+            Architecture::X86_64 { .. },
+            Some(OS::Darwin | OS::MacOSX | OS::IOS | OS::TvOS | OS::WatchOS | OS::Win32),
+            _,
+        ) => 128,
+        (Architecture::X86_64 { .. }, _, _) => 64,
+        // cmpxchg8b was introduced with the Pentium
+        (Architecture::X86_32(5..), _, _) => 64,
+        (Architecture::X86_32(_), _, _) => 32,
+        (Architecture::X86_16(_), _, _) => 16,
+        (Architecture::Aarch64 | Architecture::Aarch64Be | Architecture::Aarch64_32, _, _) => 128,
+        (Architecture::Arm | Architecture::ArmBe, _, Some(Environment::Android)) => 32,
+        (Architecture::Arm | Architecture::ArmBe, _, _) => 64,
+        (Architecture::Wasm32 | Architecture::Wasm64, _, _) => 64,
+        (
+            Architecture::Mips64
+            | Architecture::Mips64LE
+            | Architecture::PowerPC64
+            | Architecture::PowerPC64le
+            | Architecture::RiscV64
+            | Architecture::SparcV9,
+            _,
+            _,
+        ) => 64,
+        (
+            Architecture::Mips
+            | Architecture::MipsLE
+            | Architecture::PowerPC32
+            | Architecture::RiscV32
+            | Architecture::Sparc
+            | Architecture::SparcEL
+            | Architecture::M68k,
+            _,
+            _,
+        ) => 32,
+        _ => 0,
+    }
+}
+
+fn has_atomic(max_width: u32, pointer_width: u32) -> &'static [&'static str] {
+    match (max_width, max_width >= pointer_width) {
+        (128.., _) => &["8", "16", "32", "64", "128", "ptr"],
+        (64, _) => &["8", "16", "32", "64", "ptr"],
+        (32, true) => &["8", "16", "32", "ptr"],
+        (32, false) => &["8", "16", "32"],
+        (16, true) => &["8", "16", "ptr"],
+        (16, false) => &["8", "16"],
+        _ => &[],
+    }
+}
+
+fn families(targ: &CanonicalTarget) -> &'static [&'static str] {
+    let wasm = matches!(targ.arch, Architecture::Wasm32 | Architecture::Wasm64);
+
+    match targ.sys.os() {
+        Some(OS::Win32) => &["windows"],
+        Some(OS::Emscripten) => &["unix", "wasm"],
+        Some(
+            OS::Linux
+            | OS::Darwin
+            | OS::MacOSX
+            | OS::IOS
+            | OS::TvOS
+            | OS::WatchOS
+            | OS::FreeBSD
+            | OS::KFreeBSD
+            | OS::NetBSD
+            | OS::KNetBSD
+            | OS::OpenBSD
+            | OS::DragonFly
+            | OS::Solaris
+            | OS::KOpenSolaris
+            | OS::SunOS
+            | OS::Haiku
+            | OS::Fuchsia
+            | OS::AIX
+            | OS::Hurd
+            | OS::QNX
+            | OS::Minix
+            | OS::Ultrix
+            | OS::IRIX
+            | OS::HPUX
+            | OS::SysV,
+        ) => &["unix"],
+        _ if wasm => &["wasm"],
+        _ => &[],
+    }
+}
+
+impl CanonicalTarget {
+    ///
+    /// Returns the cfgs rustc defines when compiling for this target.
+    ///
+    /// Only the features that determine the architecture are included in the target features, see [`TargetCfg::features`].
+    /// Targets which rustc does not support are described as closely as possible
+    pub fn rustc_cfgs(&self) -> RustcCfgs {
+        let (env, abi) = rustc::env_abi(self.arch, self.sys);
+        let width = rustc::pointer_width(self.arch, self.sys.env());
+
+        RustcCfgs {
+            target: TargetCfg {
+                arch: rustc::arch(self.arch),
+                vendor: self.vendor.unwrap_or(Vendor::Unknown).canonical_name(),
+                os: rustc::os(self.sys),
+                env,
+                abi,
+                endian: if rustc::is_big_endian(self.arch) {
+                    "big"
+                } else {
+                    "little"
+                },
+                pointer_width: match width {
+                    16 => "16",
+                    32 => "32",
+                    _ => "64",
+                },
+                features: rustc::baseline_features(self.arch),
+            },
+            families: families(self),
+            has_atomic: has_atomic(max_atomic_width(self), width),
+        }
+    }
+}
+
+fn is_ident(s: &str) -> bool {
+    let mut chars = s.chars();
+
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+///
+/// A single cfg, either a bare name (such as `unix`) or a key-value pair (such as `target_os = "linux"`)
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Cfg<'a> {
+    /// The name of the cfg
+    pub name: &'a str,
+    /// The value of the cfg, or [`None`] for a bare name
+    pub value: Option<&'a str>,
+}
+
+impl<'a> Cfg<'a> {
+    /// Parses a line printed by `rustc --print cfg`, such as `unix` or `target_os="linux"`
+    pub fn parse(line: &'a str) -> Result<Self, UnknownError> {
+        let line = line.trim();
+
+        let (name, value) = match line.split_once('=') {
+            Some((name, value)) => {
+                let value = value
+                    .trim()
+                    .strip_prefix('"')
+                    .and_then(|value| value.strip_suffix('"'))
+                    .ok_or(UnknownError)?;
+                (name.trim(), Some(value))
+            }
+            None => (line, None),
+        };
+
+        if !is_ident(name) {
+            return Err(UnknownError);
+        }
+
+        Ok(Self { name, value })
+    }
+}
+
+impl Display for Cfg<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self.value {
+            Some(value) => f.write_fmt(format_args!("{}=\"{}\"", self.name, value)),
+            None => f.write_str(self.name),
+        }
+    }
+}
+
+/// The cfgs generated by [`CanonicalTarget::rustc_cfgs`]. Other cfgs printed by rustc, such as `target_feature` or `debug_assertions`,
+///  do not depend on the target tuple
+#[cfg(feature = "alloc")]
+const TUPLE_CFGS: &[&str] = &[
+    "target_arch",
+    "target_vendor",
+    "target_os",
+    "target_env",
+    "target_abi",
+    "target_endian",
+    "target_pointer_width",
+    "target_family",
+    "target_has_atomic",
+    "unix",
+    "windows",
+];
+
+///
+/// The target reconstructed from the output of `rustc --print cfg`, by [`parse_print_cfg`]
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct PrintedCfgs<'a> {
+    /// The target that best matches the cfgs
+    pub target: CanonicalTarget,
+    /// The cfgs which depend on the target tuple, but which [`CanonicalTarget::rustc_cfgs`] does not generate for [`Self::target`],
+    ///  such as an operating system unknown to the library
+    pub unaccounted: Vec<Cfg<'a>>,
+}
+
+///
+/// Parses the output of `rustc --print cfg`, and reconstructs the target it describes.
+///
+/// Returns an error if a line is not a well-formed cfg, or if `target_arch` is not set
+#[cfg(feature = "alloc")]
+pub fn parse_print_cfg(text: &str) -> Result<PrintedCfgs<'_>, UnknownError> {
+    let cfgs = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(Cfg::parse)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let value = |name: &str| {
+        cfgs.iter()
+            .find(|cfg| cfg.name == name)
+            .and_then(|cfg| cfg.value)
+            .unwrap_or("")
+    };
+
+    let features = cfgs
+        .iter()
+        .filter(|cfg| cfg.name == "target_feature")
+        .filter_map(|cfg| cfg.value)
+        .collect::<Vec<_>>();
+
+    let cfg = TargetCfg {
+        arch: value("target_arch"),
+        vendor: value("target_vendor"),
+        os: value("target_os"),
+        env: value("target_env"),
+        abi: value("target_abi"),
+        endian: value("target_endian"),
+        pointer_width: value("target_pointer_width"),
+        features: &features,
+    };
+
+    if cfg.arch.is_empty() {
+        return Err(UnknownError);
+    }
+
+    let target = cfg.to_target();
+    let generated = target.rustc_cfgs();
+
+    let unaccounted = cfgs
+        .iter()
+        .copied()
+        .filter(|cfg| TUPLE_CFGS.contains(&cfg.name) && !generated.contains(cfg.name, cfg.value))
+        .collect();
+
+    Ok(PrintedCfgs {
+        target,
+        unaccounted,
+    })
+}
+
+///
+/// A cfg expression, as used by `#[cfg]` and by Cargo's `[target.'cfg(...)']` tables,
+///  such as `all(unix, target_arch = "x86_64")`
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum CfgExpr<'a> {
+    /// A single cfg, such as `unix` or `target_os = "linux"`
+    Cfg(Cfg<'a>),
+    /// `all(...)`, which is true if every expression is true
+    All(Vec<CfgExpr<'a>>),
+    /// `any(...)`, which is true if at least one expression is true
+    Any(Vec<CfgExpr<'a>>),
+    /// `not(...)`
+    Not(Box<CfgExpr<'a>>),
+}
+
+#[cfg(feature = "alloc")]
+struct ExprParser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> ExprParser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn ident(&mut self) -> Result<&'a str, UnknownError> {
+        self.skip_ws();
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        let ident = &rest[..len];
+
+        if !is_ident(ident) {
+            return Err(UnknownError);
+        }
+
+        self.pos += len;
+        Ok(ident)
+    }
+
+    fn string(&mut self) -> Result<&'a str, UnknownError> {
+        if !self.eat('"') {
+            return Err(UnknownError);
+        }
+
+        let rest = self.rest();
+        let len = rest.find('"').ok_or(UnknownError)?;
+        self.pos += len + 1;
+        Ok(&rest[..len])
+    }
+
+    fn list(&mut self) -> Result<Vec<CfgExpr<'a>>, UnknownError> {
+        if !self.eat('(') {
+            return Err(UnknownError);
+        }
+
+        let mut list = Vec::new();
+
+        while !self.eat(')') {
+            list.push(self.expr()?);
+
+            if !self.eat(',') {
+                if !self.eat(')') {
+                    return Err(UnknownError);
+                }
+                break;
+            }
+        }
+
+        Ok(list)
+    }
+
+    fn expr(&mut self) -> Result<CfgExpr<'a>, UnknownError> {
+        let name = self.ident()?;
+
+        if self.eat('=') {
+            let value = self.string()?;
+            return Ok(CfgExpr::Cfg(Cfg {
+                name,
+                value: Some(value),
+            }));
+        }
+
+        self.skip_ws();
+        if !self.rest().starts_with('(') {
+            return Ok(CfgExpr::Cfg(Cfg { name, value: None }));
+        }
+
+        match name {
+            "all" => Ok(CfgExpr::All(self.list()?)),
+            "any" => Ok(CfgExpr::Any(self.list()?)),
+            "not" => {
+                let mut list = self.list()?;
+                if list.len() != 1 {
+                    return Err(UnknownError);
+                }
+                Ok(CfgExpr::Not(Box::new(list.remove(0))))
+            }
+            _ => Err(UnknownError),
+        }
+    }
+}
+
+///
+/// The result of evaluating a [`CfgExpr`] against a target, by [`CfgExpr::eval`]
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct CfgEval<'a> {
+    /// Whether the expression is true for the target, or [`None`] if that depends on cfgs that are not determined by the target
+    pub matches: Option<bool>,
+    /// The cfgs in the expression that are not determined by the target, such as `feature = "std"` or `debug_assertions`
+    pub unknown: Vec<Cfg<'a>>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> CfgExpr<'a> {
+    /// Parses a cfg expression, optionally wrapped in `cfg(...)` as in Cargo's `[target]` tables
+    pub fn parse(s: &'a str) -> Result<Self, UnknownError> {
+        let mut parser = ExprParser { src: s, pos: 0 };
+
+        let wrapped = s
+            .trim_start()
+            .strip_prefix("cfg")
+            .map_or(false, |rest| rest.trim_start().starts_with('('));
+
+        let expr = if wrapped {
+            parser.ident()?;
+            let mut list = parser.list()?;
+            if list.len() != 1 {
+                return Err(UnknownError);
+            }
+            list.remove(0)
+        } else {
+            parser.expr()?
+        };
+
+        parser.skip_ws();
+        if !parser.rest().is_empty() {
+            return Err(UnknownError);
+        }
+
+        Ok(expr)
+    }
+
+    /// Calls `f` on each cfg in the expression
+    fn for_each_cfg(&self, f: &mut impl FnMut(Cfg<'a>)) {
+        match self {
+            CfgExpr::Cfg(cfg) => f(*cfg),
+            CfgExpr::All(list) | CfgExpr::Any(list) => {
+                list.iter().for_each(|expr| expr.for_each_cfg(f))
+            }
+            CfgExpr::Not(expr) => expr.for_each_cfg(f),
+        }
+    }
+
+    fn eval_cfgs(&self, cfgs: &RustcCfgs) -> Option<bool> {
+        match self {
+            CfgExpr::Cfg(cfg) => {
+                if TUPLE_CFGS.contains(&cfg.name) {
+                    Some(cfgs.contains(cfg.name, cfg.value))
+                } else {
+                    None
+                }
+            }
+            // `all` is false if any expression is false, and `any` is true if any expression is true.
+            // Otherwise, an expression with an unknown result makes the whole result unknown
+            CfgExpr::All(list) | CfgExpr::Any(list) => {
+                let decisive = matches!(self, CfgExpr::Any(_));
+                let mut result = Some(!decisive);
+
+                for expr in list {
+                    match expr.eval_cfgs(cfgs) {
+                        Some(b) if b == decisive => return Some(decisive),
+                        Some(_) => {}
+                        None => result = None,
+                    }
+                }
+
+                result
+            }
+            CfgExpr::Not(expr) => expr.eval_cfgs(cfgs).map(|b| !b),
+        }
+    }
+
+    ///
+    /// Evaluates the expression against the cfgs rustc defines for `targ` (see [`CanonicalTarget::rustc_cfgs`]).
+    ///
+    /// Cfgs that do not depend on the target, such as `feature = "std"`, are neither true nor false, and are reported in [`CfgEval::unknown`].
+    /// The expression matches only if its result does not depend on them
+    pub fn eval(&self, targ: &CanonicalTarget) -> CfgEval<'a> {
+        let mut unknown = Vec::new();
+        self.for_each_cfg(&mut |cfg| {
+            if !TUPLE_CFGS.contains(&cfg.name) && !unknown.contains(&cfg) {
+                unknown.push(cfg);
+            }
+        });
+
+        CfgEval {
+            matches: self.eval_cfgs(&targ.rustc_cfgs()),
+            unknown,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Display for CfgExpr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let (name, list) = match self {
+            CfgExpr::Cfg(cfg) => return cfg.fmt(f),
+            CfgExpr::All(list) => ("all", &**list),
+            CfgExpr::Any(list) => ("any", &**list),
+            CfgExpr::Not(expr) => ("not", core::slice::from_ref(&**expr)),
+        };
+
+        f.write_str(name)?;
+        f.write_str("(")?;
+        for (i, expr) in list.iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            expr.fmt(f)?;
+        }
+        f.write_str(")")
+    }
+}
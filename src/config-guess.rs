@@ -0,0 +1,39 @@
+use target_tuples::*;
+
+pub fn main() {
+    let mut args = std::env::args();
+    let bin = args.next().unwrap();
+    if let Some(name) = args.next() {
+        match &*name {
+            "--help" => {
+                println!("Usage: {} [OPTION]", bin);
+                println!("Prints a guess of the canonical target of the system the program is running on");
+                println!("Options:");
+                println!("\t--help: Prints this message, and exits");
+                println!("\t--version: Prints version information, and exists");
+                println!("\t--gnu: Prints the target in the same form as GNU config.guess");
+            }
+            "--version" => {
+                println!("config.guess v{}", env!("CARGO_PKG_VERSION"));
+                println!("Copyright (C) 2020 Connor Horman, this program is a free software, dual-licensed under the terms of the Apache v2 and the MIT license");
+                println!("This program is provided AS-IS, without any warranty.");
+            }
+            "--gnu" => guess(Canonicalization::GnuCompat),
+            x => {
+                eprintln!("Unknown option {}", x);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        guess(Canonicalization::Native)
+    }
+}
+
+fn guess(mode: Canonicalization) {
+    if let Ok(t) = CanonicalTarget::guess_host() {
+        println!("{}", t.display(mode));
+    } else {
+        eprintln!("Unable to guess the target of this system");
+        std::process::exit(1);
+    }
+}
@@ -6,20 +6,32 @@ pub fn main() {
     if let Some(name) = args.next() {
         match &*name {
             "--help" => {
-                println!("Usage: {} [OPTION]|<target>", bin);
+                println!("Usage: {} [OPTION]|[--gnu] <target>", bin);
                 println!("Converts a target tuple into canonical form");
                 println!("Options:");
                 println!("\t--help: Prints this message, and exits");
                 println!("\t--version: Prints version information, and exists");
+                println!("\t--gnu: Prints the target in the same form as GNU config.sub");
             }
             "--version" => {
                 println!("config.sub v{}", env!("CARGO_PKG_VERSION"));
                 println!("Copyright (C) 2020 Connor Horman, this program is a free software, dual-licensed under the terms of the Apache v2 and the MIT license");
                 println!("This program is provided AS-IS, without any warranty.");
             }
+            "--gnu" => {
+                let Some(x) = args.next() else {
+                    std::process::exit(1);
+                };
+                if let Ok(t) = alias::canonicalize_gnu(&x) {
+                    println!("{}", t);
+                } else {
+                    eprintln!("Unsupported target {}", x);
+                    std::process::exit(1);
+                }
+            }
             x => {
-                if let Ok(t) = TargetRef::try_parse(x) {
-                    println!("{}", t.canonical());
+                if let Ok(t) = CanonicalTarget::from_alias(x) {
+                    println!("{}", t);
                 } else {
                     eprintln!("Unsupported target {}", x);
                     std::process::exit(1);
@@ -0,0 +1,67 @@
+use core::fmt::{Display, Formatter, Result};
+
+use crate::{pieces::*, CanonicalTarget};
+
+///
+/// Formats `targ` the way GNU config.sub prints the canonical form of the same target.
+/// `os_version` is the version config.sub gives an operating system it assumed, see [`canonicalize_gnu`][crate::alias::canonicalize_gnu]
+pub(crate) fn fmt_gnu(
+    targ: &CanonicalTarget,
+    os_version: Option<&str>,
+    f: &mut Formatter<'_>,
+) -> Result {
+    let sys = targ.sys;
+
+    targ.arch.fmt(f)?;
+    f.write_str("-")?;
+
+    // config.sub reads `<arch>-none-<objfmt>` as the `none` vendor, rather than the `none` operating system
+    if targ.vendor.is_none()
+        && sys.os() == Some(OS::None)
+        && (sys.env().is_some() || sys.object_format().is_some())
+    {
+        f.write_str("none-")?;
+        return fmt_rest(sys, f);
+    }
+
+    // config.sub gives no vendor to the few architectures it reads on their own, such as `w65`
+    if targ.vendor.is_none() && targ.arch == Architecture::Wc65c816 {
+        return sys.fmt(f);
+    }
+
+    let vendor = match (targ.guess_vendor(), sys.os()) {
+        (Vendor::Unknown, Some(OS::AIX)) => Vendor::IBM,
+        (Vendor::Unknown, Some(OS::MacOSX)) => Vendor::Apple,
+        (vendor, _) => vendor,
+    };
+
+    vendor.fmt(f)?;
+    f.write_str("-")?;
+
+    match (sys.kernel(), sys.os()) {
+        (None, Some(OS::Linux)) if sys.env().is_none() && sys.object_format().is_none() => {
+            f.write_str("linux-gnu")
+        }
+        (None, Some(OS::Solaris)) => {
+            f.write_str("solaris2")?;
+            if sys.env().is_some() || sys.object_format().is_some() {
+                f.write_str("-")?;
+                fmt_rest(sys, f)?;
+            }
+            Ok(())
+        }
+        _ => {
+            sys.fmt(f)?;
+            // Only an operating system assumed by config.sub has a version, in which case it is the whole system
+            if let Some(version) = os_version {
+                f.write_str(version)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Formats the environment and object format of `sys`, without the operating system
+fn fmt_rest(sys: System, f: &mut Formatter<'_>) -> Result {
+    System::from_pieces(None, sys.env(), sys.object_format()).fmt(f)
+}
@@ -0,0 +1,306 @@
+//! Detection of the machine the program is running on, in the manner of GNU config.guess
+//!
+//! The information used to guess the host is supplied by a [`HostProbe`]. [`SystemProbe`] queries the running system,
+//!  and other implementations may be used to guess the target of a different machine, or to replay recorded information.
+
+use std::process::Command;
+use std::string::String;
+
+use crate::{pieces::*, CanonicalTarget};
+
+///
+/// Information about the header of an ELF executable, as read by [`ElfInfo::parse`]
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct ElfInfo {
+    /// Whether the executable is `ELFCLASS64`
+    pub is_64bit: bool,
+    /// Whether the executable is `ELFDATA2MSB`
+    pub big_endian: bool,
+    /// The architecture of the executable (`e_machine`), such as `62` for `EM_X86_64`
+    pub machine: u16,
+    /// The program interpreter (`PT_INTERP`) requested by the executable, if any
+    pub interpreter: Option<String>,
+}
+
+impl ElfInfo {
+    /// Reads the header of an ELF executable. Returns [`None`] if `bytes` is not a well-formed ELF file
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        if bytes.get(..4)? != b"\x7fELF" {
+            return None;
+        }
+
+        let is_64bit = match bytes.get(4)? {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+
+        let big_endian = match bytes.get(5)? {
+            1 => false,
+            2 => true,
+            _ => return None,
+        };
+
+        let read = |off: usize, len: usize| -> Option<u64> {
+            let field = bytes.get(off..off.checked_add(len)?)?;
+            let mut val = 0u64;
+            for i in 0..len {
+                let byte = if big_endian {
+                    field[i]
+                } else {
+                    field[len - i - 1]
+                };
+                val = (val << 8) | u64::from(byte);
+            }
+            Some(val)
+        };
+
+        let machine = read(18, 2)? as u16;
+        let word = if is_64bit { 8 } else { 4 };
+
+        let phoff = usize::try_from(read(if is_64bit { 32 } else { 28 }, word)?).ok()?;
+        let phentsize = read(if is_64bit { 54 } else { 42 }, 2)? as usize;
+        let phnum = read(if is_64bit { 56 } else { 44 }, 2)? as usize;
+
+        let mut interpreter = None;
+
+        for i in 0..phnum {
+            // The offsets come from the file, so a malformed file must not overflow them
+            let ph = phoff.checked_add(i.checked_mul(phentsize)?)?;
+            const PT_INTERP: u64 = 3;
+            if read(ph, 4)? != PT_INTERP {
+                continue;
+            }
+
+            let (offset, filesz) = if is_64bit {
+                (read(ph.checked_add(8)?, 8)?, read(ph.checked_add(32)?, 8)?)
+            } else {
+                (read(ph.checked_add(4)?, 4)?, read(ph.checked_add(16)?, 4)?)
+            };
+
+            let start = usize::try_from(offset).ok()?;
+            let end = usize::try_from(offset.checked_add(filesz)?).ok()?;
+            let path = bytes.get(start..end)?;
+            let path = path.split(|&b| b == 0).next()?;
+            interpreter = Some(String::from_utf8_lossy(path).into_owned());
+            break;
+        }
+
+        Some(Self {
+            is_64bit,
+            big_endian,
+            machine,
+            interpreter,
+        })
+    }
+}
+
+///
+/// A source of information about a machine, used by [`CanonicalTarget::guess_host_with`]
+pub trait HostProbe {
+    /// The name of the operating system, as given by `uname -s` (such as `Linux` or `Darwin`)
+    fn sysname(&self) -> Option<String>;
+
+    /// The name of the hardware, as given by `uname -m` (such as `x86_64` or `armv7l`)
+    fn machine(&self) -> Option<String>;
+
+    /// The release of the operating system, as given by `uname -r`
+    fn release(&self) -> Option<String>;
+
+    /// The contents of `/proc/cpuinfo`, on systems that provide it
+    fn cpuinfo(&self) -> Option<String>;
+
+    /// The header of `/bin/sh`, which is used to determine the C library and ABI of the userland
+    fn shell_elf(&self) -> Option<ElfInfo>;
+}
+
+///
+/// A [`HostProbe`] which queries the machine the program is running on
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemProbe;
+
+fn uname(flag: &str) -> Option<String> {
+    let out = Command::new("uname").arg(flag).output().ok()?;
+
+    if !out.status.success() {
+        return None;
+    }
+
+    let out = String::from_utf8(out.stdout).ok()?;
+    Some(out.trim().into())
+}
+
+impl HostProbe for SystemProbe {
+    fn sysname(&self) -> Option<String> {
+        uname("-s")
+    }
+
+    fn machine(&self) -> Option<String> {
+        uname("-m")
+    }
+
+    fn release(&self) -> Option<String> {
+        uname("-r")
+    }
+
+    fn cpuinfo(&self) -> Option<String> {
+        std::fs::read_to_string("/proc/cpuinfo").ok()
+    }
+
+    fn shell_elf(&self) -> Option<ElfInfo> {
+        ElfInfo::parse(&std::fs::read("/bin/sh").ok()?)
+    }
+}
+
+fn guess_arch(machine: &str, elf: Option<&ElfInfo>) -> Result<Architecture, UnknownError> {
+    let big_endian = elf.map(|elf| elf.big_endian);
+
+    Ok(match machine {
+        "amd64" | "x86_64" => Architecture::X86_64 { microarch: 1 },
+        "i86pc" if elf.map_or(false, |elf| elf.is_64bit) => Architecture::X86_64 { microarch: 1 },
+        "i86pc" => Architecture::X86_32(3),
+        x if x.len() == 4 && x.starts_with('i') && x.ends_with("86") => match x.as_bytes()[1] {
+            b @ b'3'..=b'7' => Architecture::X86_32(b - b'0'),
+            _ => return Err(UnknownError),
+        },
+        x if x.starts_with("armv") || x == "arm" => {
+            if x.ends_with('b') || big_endian == Some(true) {
+                Architecture::ArmBe
+            } else {
+                Architecture::Arm
+            }
+        }
+        "arm64" => Architecture::Aarch64,
+        "mips" if big_endian == Some(false) => Architecture::MipsLE,
+        "mips64" if big_endian == Some(false) => Architecture::Mips64LE,
+        "ppc" | "powerpc" | "macppc" => Architecture::PowerPC32,
+        "ppc64" => Architecture::PowerPC64,
+        "ppc64le" => Architecture::PowerPC64le,
+        "sparc64" => Architecture::SparcV9,
+        x if x.starts_with("sun4") => Architecture::Sparc,
+        x => x.parse()?,
+    })
+}
+
+/// The `e_machine` of x86_64 executables, including x32 executables
+const EM_X86_64: u16 = 62;
+
+/// Determines the C library (and ABI) of a Linux userland, from the interpreter of `/bin/sh`
+fn guess_linux_env(
+    arch: Architecture,
+    elf: Option<&ElfInfo>,
+    cpuinfo: Option<&str>,
+) -> Environment {
+    let interp = elf.and_then(|elf| elf.interpreter.as_deref()).unwrap_or("");
+    let interp = interp.rsplit('/').next().unwrap_or(interp);
+
+    let musl = interp.starts_with("ld-musl");
+    let uclibc = interp.starts_with("ld-uClibc");
+
+    match arch {
+        Architecture::Arm | Architecture::ArmBe => {
+            let hard_float = if interp.is_empty() {
+                cpuinfo.map_or(false, |cpuinfo| {
+                    cpuinfo.lines().any(|line| {
+                        line.starts_with("Features")
+                            && line
+                                .split_whitespace()
+                                .any(|feature| feature.starts_with("vfp"))
+                    })
+                })
+            } else {
+                interp.contains("armhf")
+            };
+
+            match (musl, hard_float) {
+                _ if uclibc => Environment::UClibc,
+                (true, true) => Environment::MuslEABIHF,
+                (true, false) => Environment::MuslEABI,
+                (false, true) => Environment::GNUEABIHF,
+                (false, false) => Environment::GNUEABI,
+            }
+        }
+        _ if musl => Environment::Musl,
+        _ if uclibc => Environment::UClibc,
+        // Like config.guess, only an `elf32-x86-64` shell is x32, and not an i386 userland on an x86_64 kernel
+        Architecture::X86_64 { .. }
+            if elf.map_or(false, |elf| !elf.is_64bit && elf.machine == EM_X86_64) =>
+        {
+            Environment::GNUX32
+        }
+        _ => Environment::GNU,
+    }
+}
+
+impl CanonicalTarget {
+    ///
+    /// Guesses the target of the machine the program is running on, in the same manner as GNU config.guess.
+    ///
+    /// This is equivalent to [`CanonicalTarget::guess_host_with`] using [`SystemProbe`]
+    pub fn guess_host() -> Result<Self, UnknownError> {
+        Self::guess_host_with(&SystemProbe)
+    }
+
+    ///
+    /// Guesses the target of the machine described by `probe`, in the same manner as GNU config.guess.
+    ///
+    /// Returns an error if the operating system or the hardware is not known to the library
+    pub fn guess_host_with<P: HostProbe + ?Sized>(probe: &P) -> Result<Self, UnknownError> {
+        let sysname = probe.sysname().ok_or(UnknownError)?;
+        let machine = probe.machine().ok_or(UnknownError)?;
+
+        let elf = probe.shell_elf();
+
+        let mut arch = guess_arch(&machine, elf.as_ref())?;
+
+        let (vendor, sys) = match &*sysname {
+            "Linux" => {
+                let cpuinfo = probe.cpuinfo();
+                let env = guess_linux_env(arch, elf.as_ref(), cpuinfo.as_deref());
+                let vendor = match arch {
+                    Architecture::X86_16(_)
+                    | Architecture::X86_32(_)
+                    | Architecture::X86_64 { .. } => Vendor::PC,
+                    _ => Vendor::Unknown,
+                };
+                (vendor, System::from_os_env(OS::Linux, env))
+            }
+            "GNU" => (Vendor::Unknown, System::from_os(OS::Hurd)),
+            "GNU/kFreeBSD" => (
+                Vendor::Unknown,
+                System::from_os_env(OS::KFreeBSD, Environment::GNU),
+            ),
+            "Darwin" => (Vendor::Apple, System::from_os(OS::Darwin)),
+            "FreeBSD" => {
+                // config.guess reports 32-bit x86 FreeBSD as i586
+                if let Architecture::X86_32(_) = arch {
+                    arch = Architecture::X86_32(5);
+                }
+                (Vendor::Unknown, System::from_os(OS::FreeBSD))
+            }
+            "NetBSD" => (Vendor::Unknown, System::from_os(OS::NetBSD)),
+            "OpenBSD" => (Vendor::Unknown, System::from_os(OS::OpenBSD)),
+            "DragonFly" => (Vendor::Unknown, System::from_os(OS::DragonFly)),
+            "Haiku" => (Vendor::Unknown, System::from_os(OS::Haiku)),
+            "SunOS" => {
+                let vendor = match arch {
+                    Architecture::X86_32(_) | Architecture::X86_64 { .. } => Vendor::PC,
+                    _ => Vendor::Sun,
+                };
+                let release = probe.release().unwrap_or_default();
+                if release.starts_with("5.") {
+                    (vendor, System::from_os(OS::Solaris))
+                } else {
+                    (vendor, System::from_os(OS::SunOS))
+                }
+            }
+            _ => return Err(UnknownError),
+        };
+
+        Ok(CanonicalTarget {
+            arch,
+            vendor: Some(vendor),
+            sys,
+        })
+    }
+}
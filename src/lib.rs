@@ -4,13 +4,35 @@
 #[cfg(feature = "alloc")]
 extern crate alloc;
 
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod alias;
+pub mod bits;
+#[cfg(feature = "std")]
+pub mod build;
+#[cfg(feature = "capi")]
+pub mod capi;
+pub mod cfg;
+mod gnu;
+#[cfg(feature = "std")]
+pub mod host;
+pub mod pattern;
 pub mod pieces;
+#[cfg(feature = "serde")]
+mod serde_impls;
+#[cfg(feature = "target-spec")]
+pub mod spec;
+#[cfg(feature = "serde")]
+pub mod structured;
 
 use core::{ops::Deref, str::FromStr};
 
 use pieces::*;
 
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+///
+/// Targets are ordered by architecture, then by vendor, and then by system. See the [`Ord`] implementations of each piece
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CanonicalTarget {
     pub arch: Architecture,
     pub vendor: Option<Vendor>,
@@ -18,7 +40,12 @@ pub struct CanonicalTarget {
 }
 
 impl CanonicalTarget {
-    pub fn guess_vendor(&self) -> Vendor {
+    /// Constructs a target from its pieces. Usable in `const` contexts
+    pub const fn new(arch: Architecture, vendor: Option<Vendor>, sys: System) -> Self {
+        Self { arch, vendor, sys }
+    }
+
+    pub const fn guess_vendor(&self) -> Vendor {
         if let Some(vendor) = self.vendor {
             return vendor;
         }
@@ -28,31 +55,88 @@ impl CanonicalTarget {
                 _,
             ) => Vendor::PC,
             (Architecture::Wc65c816, _) => Vendor::WDC,
+            (_, Some(OS::SunOS)) => Vendor::Sun,
+            (_, Some(OS::HPUX)) => Vendor::HP,
             _ => Vendor::Unknown,
         }
     }
+
+    ///
+    /// Returns the object format of the target. If the system does not specify one,
+    ///  this is the format normally used by the operating system or architecture
+    pub const fn object_format(&self) -> ObjectFormat {
+        if let Some(objfmt) = self.sys.object_format() {
+            return objfmt;
+        }
+
+        match (self.arch, self.sys.os()) {
+            (Architecture::Wasm32 | Architecture::Wasm64, _) => ObjectFormat::Wasm,
+            (_, Some(OS::Darwin | OS::MacOSX | OS::IOS | OS::TvOS | OS::WatchOS)) => {
+                ObjectFormat::MachO
+            }
+            (_, Some(OS::Win32)) => ObjectFormat::Coff,
+            (_, Some(OS::AIX)) => ObjectFormat::XCoff,
+            (_, Some(OS::ZOS)) => ObjectFormat::Goff,
+            _ => ObjectFormat::Elf,
+        }
+    }
+
+    /// The target matched by [`match_targets!`]. Takes priority over [`__MatchTarget`], so that matching on a target works in const context
+    #[doc(hidden)]
+    pub const fn __match_target(&self) -> Result<CanonicalTarget, core::convert::Infallible> {
+        Ok(*self)
+    }
+
+    /// Parses a target, additionally accepting the legacy machine names recognized by GNU config.sub,
+    ///  such as `sun4` or `pc98-linux`. See [`alias::expand`]
+    pub fn from_alias(s: &str) -> Result<Self, UnknownError> {
+        s.parse().or_else(|_| alias::expand(s))
+    }
+
+    /// Returns a value that formats the target according to `mode`.
+    /// Formatting with [`Canonicalization::Native`] is the same as formatting the target itself
+    pub fn display(&self, mode: Canonicalization) -> DisplayTarget<'_> {
+        DisplayTarget { targ: self, mode }
+    }
+}
+
+///
+/// The conventions followed when printing the canonical form of a target
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Canonicalization {
+    /// The canonical form used by this library
+    Native,
+    /// Matches the output of the GNU config.sub script shipped with this library,
+    ///  including its vendor defaults and the `-gnu` suffix on Linux.
+    ///
+    /// A target does not record the version of its operating system, so one that config.sub assumes for a legacy machine is not printed,
+    ///  such as `sparc-sun-sunos` rather than `sparc-sun-sunos4.1.1` for `sun4`. [`alias::canonicalize_gnu`] keeps the version
+    GnuCompat,
+}
+
+///
+/// Formats a target according to a [`Canonicalization`]. Returned by [`CanonicalTarget::display`]
+#[derive(Copy, Clone, Debug)]
+pub struct DisplayTarget<'a> {
+    targ: &'a CanonicalTarget,
+    mode: Canonicalization,
+}
+
+impl<'a> core::fmt::Display for DisplayTarget<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.mode {
+            Canonicalization::Native => self.targ.fmt(f),
+            Canonicalization::GnuCompat => gnu::fmt_gnu(self.targ, None, f),
+        }
+    }
 }
 
 impl core::str::FromStr for CanonicalTarget {
     type Err = UnknownError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (arch, rest) = s.split_once('-').ok_or(UnknownError)?;
-
-        let arch = arch.parse::<Architecture>()?;
-
-        let (vendor, sys) = if let Some((a, b)) = rest.split_once('-') {
-            if b.contains('-') {
-                // 4 component, this is vendor-os-env
-                (Some(Vendor::parse(a)), b.parse::<System>()?)
-            } else if let Ok(sys) = rest.parse::<System>() {
-                (None, sys)
-            } else {
-                (Some(Vendor::parse(a)), b.parse::<System>()?)
-            }
-        } else {
-            (None, rest.parse::<System>()?)
-        };
+        let (arch, vendor, sys) = pieces::parse_target(s)?;
 
         Ok(CanonicalTarget { arch, vendor, sys })
     }
@@ -71,10 +155,12 @@ impl core::fmt::Display for CanonicalTarget {
     }
 }
 
-#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+///
+/// References are ordered by their canonical target, and then by their exact name
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TargetRef<'a> {
-    exact: &'a str,
     canon: CanonicalTarget,
+    exact: &'a str,
 }
 
 impl<'a> Deref for TargetRef<'a> {
@@ -99,6 +185,11 @@ impl<'a> TargetRef<'a> {
         }
     }
 
+    #[doc(hidden)]
+    pub const fn __from_parts(exact: &'a str, canon: CanonicalTarget) -> Self {
+        Self { exact, canon }
+    }
+
     pub fn exact(&self) -> &'a str {
         self.exact
     }
@@ -115,13 +206,15 @@ mod feature_alloc {
     use crate::{pieces::UnknownError, CanonicalTarget, TargetRef};
     use alloc::string::{String, ToString};
 
-    #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+    ///
+    /// Targets are ordered by their canonical target, and then by their exact name
+    #[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
     pub struct OwnedTarget {
-        exact: String,
         canon: CanonicalTarget,
+        exact: String,
     }
 
-    impl<'a> Deref for OwnedTarget {
+    impl Deref for OwnedTarget {
         type Target = CanonicalTarget;
 
         fn deref(&self) -> &CanonicalTarget {
@@ -170,8 +263,185 @@ pub use core as __core;
 #[doc(hidden)]
 pub use target_tuples_macro::__match_targets;
 
+///
+/// The strings accepted by [`match_targets!`], which are parsed, with an `!unparsed` arm handling the error.
+///
+/// The target is found by method call, so any type that dereferences to a [`str`] is accepted. A type that dereferences to a [`CanonicalTarget`]
+///  uses the inherent `__match_target` of [`CanonicalTarget`] instead, which is `const`, and cannot fail
+#[doc(hidden)]
+pub trait __MatchTarget {
+    type Error;
+
+    fn __match_target(&self) -> Result<CanonicalTarget, Self::Error>;
+}
+
+impl __MatchTarget for str {
+    type Error = UnknownError;
+
+    fn __match_target(&self) -> Result<CanonicalTarget, Self::Error> {
+        self.parse()
+    }
+}
+
+/// Called by [`match_targets!`] with the error of a target that failed to parse, when there is no `!unparsed` arm.
+/// This only compiles if the target cannot fail to parse
+#[doc(hidden)]
+pub const fn __unparsed_arm_required(err: core::convert::Infallible) -> ! {
+    match err {}
+}
+
+#[doc(hidden)]
+pub use target_tuples_macro::__target;
+
+///
+/// Matches a target against patterns of target tuples, such as `x86_64-*-linux-gnu`. The patterns are validated at compile time.
+///
+/// Each arm consists of one or more patterns separated by `|`, an optional `if` guard, and a body.
+/// A pattern is written in the syntax accepted by [`TargetPattern`][pattern::TargetPattern], and matches the same targets.
+/// This includes families, such as `{x86}-*-{bsd}` or `{i686+}-*`, which expand to every architecture or operating system in the family.
+/// The target may be any value that dereferences to a [`CanonicalTarget`], such as a [`TargetRef`], or to a [`str`], which is parsed.
+/// A target that fails to parse is handled by an arm of the form `!unparsed(err) => ...`, which binds the [`UnknownError`][pieces::UnknownError],
+///  and is required when matching on a string:
+///
+/// ```
+/// use target_tuples::match_targets;
+///
+/// let name = "x86_64-pc-linux-gnu";
+///
+/// match_targets! {
+///     name {
+///         *-*-linux-* => {}
+///         !unparsed(err) => panic!("{name} is not a target: {err:?}"),
+///         * => panic!("Unexpected target {name}"),
+///     }
+/// }
+/// ```
+///
+/// Matching on a [`CanonicalTarget`] works in const context, so the macro may compute constants for each target:
+///
+/// ```
+/// use target_tuples::{match_targets, target, CanonicalTarget};
+///
+/// const fn page_size(targ: CanonicalTarget) -> usize {
+///     match_targets! {
+///         targ {
+///             aarch64-apple-* => 16384,
+///             * => 4096,
+///         }
+///     }
+/// }
+///
+/// const MAC: usize = page_size(target!("aarch64-apple-darwin"));
+///
+/// assert_eq!(MAC, 16384);
+/// ```
+///
+/// A pattern whose components are not all identifiers, such as `65816-*-snes-elf` or `*-*-darwin21.0`, may be written as a string literal,
+///  such as `"65816-*-snes-elf"`. The components of such a pattern cannot be bound.
+/// A string literal only changes how the pattern is written, so its components must still be known to the library.
+/// Names that include a version of the Arm architecture, such as `thumbv8m.main-none-eabi`, are not supported, since the library
+///  does not distinguish versions of Arm.
+///
+/// ```compile_fail
+/// # use target_tuples::{match_targets, target};
+/// let targ = target!("arm-none-eabi");
+///
+/// match_targets! {
+///     targ {
+///         "thumbv8m.main-*-eabi" => {}
+///         * => {}
+///     }
+/// }
+/// ```
+///
+/// A vendor that is not known to the library is a compile error, unless it is spelled `unknown`, or written as a raw identifier for a custom vendor.
+/// A target does not record the name of a custom vendor, which is parsed as [`Vendor::Unknown`][pieces::Vendor::Unknown].
+/// So a raw identifier is the same as `unknown`, and matches every target with a vendor unknown to the library, not only the one it names:
+///
+/// ```
+/// use target_tuples::{match_targets, target};
+///
+/// let targ = target!("x86_64-r#othercorp-linux-gnu");
+///
+/// match_targets! {
+///     targ {
+///         x86_64-r#mycorp-linux-gnu => {}
+///         * => panic!("Unexpected target {}", targ)
+///     }
+/// }
+/// ```
+///
+/// ```compile_fail
+/// # use target_tuples::{match_targets, target};
+/// let targ = target!("x86_64-apple-darwin");
+///
+/// match_targets! {
+///     targ {
+///         x86_64-aple-darwin => {}
+///         * => {}
+///     }
+/// }
+/// ```
+///
+/// The architecture, vendor, and (in patterns of 4 components) the operating system and environment may be bound to a name with `name @ component`.
+/// The environment is bound as an [`Option<Environment>`][pieces::Environment], and the other pieces are bound as themselves.
+/// A family of operating systems in a pattern of 3 components, such as `os @ {bsd}`, binds the operating system.
+///
+/// ```
+/// use target_tuples::{match_targets, target};
+///
+/// let targ = target!("x86_64-pc-linux-musl");
+///
+/// match_targets! {
+///     targ {
+///         arch @ *-*-linux-gnu | arch @ *-*-linux-musl if arch.family() == "x86" => {
+///             assert_eq!(arch.canonical_name(), "x86_64");
+///         }
+///         * => panic!("Unexpected target {}", targ)
+///     }
+/// }
+/// ```
+///
+/// An arm whose patterns only match targets matched by earlier arms without guards is reported as a warning, as is such a pattern in an arm that is otherwise reachable.
+/// The warnings are reported as uses of a deprecated item, and may be silenced with `#[allow(deprecated)]`.
+///
+/// ```compile_fail
+/// #![deny(deprecated)]
+/// # use target_tuples::{match_targets, target};
+/// # fn main() {
+/// let targ = target!("x86_64-pc-linux-gnu");
+///
+/// match_targets! {
+///     targ {
+///         x86_64-*-linux-* => {}
+///         x86_64-pc-linux-gnu => {} // Unreachable
+///         * => {}
+///     }
+/// }
+/// # }
+/// ```
+///
+/// Writing `#[require_wildcard]` before the target requires the last arm to match every target, with `*` and no guard:
+///
+/// ```compile_fail
+/// # use target_tuples::{match_targets, target};
+/// let targ = target!("x86_64-pc-linux-gnu");
+///
+/// match_targets! {
+///     #[require_wildcard]
+///     targ {
+///         x86_64-*-linux-* => {}
+///     }
+/// }
+/// ```
 #[macro_export]
 macro_rules! match_targets {
+    {
+        #[$opt:ident]
+        $($rest:tt)*
+    } => {
+        $crate::__match_targets!([$crate] #[$opt] $($rest)*)
+    };
     {
         $expr:tt {
             $($inner:tt)*
@@ -182,3 +452,93 @@ macro_rules! match_targets {
         })
     };
 }
+
+///
+/// Compiles the item it is applied to only when compiling for a target matched by one of its patterns, such as `#[cfg_target("x86_64-*-linux-gnu")]`.
+///
+/// The patterns are those of [`match_targets!`], written as string literals or as tokens and separated by `|`, without bindings or guards.
+/// The attribute expands to `#[cfg(...)]`, with a predicate on the `target_*` cfgs that rustc defines for each piece of the pattern (see [`pieces::rustc`]):
+/// * The architecture is checked by `target_arch`, and also by `target_endian` or `target_pointer_width` if they distinguish it from another architecture with the same `target_arch`.
+///   A level of `x86_64` from `x86_64v2` is checked by the `target_feature`s it requires, so it also matches later levels, and `x86_64` matches every level.
+///   Levels of 32-bit x86 are not checked, so `i586` and `i686` match each other
+/// * The vendor is checked by `target_vendor`. Note that rustc gives most targets the vendor `unknown`, such as `x86_64-unknown-linux-gnu`, so `x86_64-pc-linux-gnu` does not match it
+/// * The system is checked by `target_os`, `target_env`, and `target_abi`, which is empty unless the environment implies an ABI, such as `eabihf`.
+///   So `arm-*-linux-gnu` does not match `arm-unknown-linux-gnueabihf`.
+///   A system without an environment, such as `linux`, only matches targets where `target_env` is empty.
+///   `cfg(target_abi)` is stable since Rust 1.78, so a pattern that names a system is a compile error on earlier compilers.
+///   A family of systems or a wildcard environment, such as `*-*-{bsd}` or `*-*-linux-*`, does not check it
+/// * The object format has no cfg, and is not checked
+///
+/// ```
+/// use target_tuples::cfg_target;
+///
+/// // Expands to #[cfg(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu", target_abi = ""))]
+/// #[cfg_target("x86_64-*-linux-gnu")]
+/// const X86_64_LINUX_GNU: bool = true;
+/// #[cfg(not(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu")))]
+/// const X86_64_LINUX_GNU: bool = false;
+///
+/// #[cfg_target({x86}-*-{bsd} | aarch64-apple-*)]
+/// fn bsd_like() -> bool {
+///     true
+/// }
+///
+/// #[cfg_target(*)]
+/// fn main() {
+///     assert_eq!(X86_64_LINUX_GNU, cfg!(all(target_arch = "x86_64", target_os = "linux", target_env = "gnu")));
+/// }
+/// ```
+pub use target_tuples_macro::cfg_target;
+
+#[doc(hidden)]
+pub use target_tuples_macro::__cfg_target_predicate;
+
+///
+/// Parses a target at compile time, and expands to a constant [`CanonicalTarget`].
+///
+/// The target is parsed in the same manner as [`CanonicalTarget::from_str`][core::str::FromStr::from_str].
+/// A target that is not known to the library is a compile error, rather than a panic at runtime.
+/// So is a vendor that is not known to the library, unless it is written as a raw identifier, such as `x86_64-r#mycorp-linux-gnu`.
+/// Such a vendor is parsed as [`Vendor::Unknown`][pieces::Vendor::Unknown], and its name is only kept by [`target_ref!`].
+///
+/// ```
+/// use target_tuples::{pieces::*, target, CanonicalTarget};
+///
+/// const LINUX: CanonicalTarget = target!("x86_64-pc-linux-gnu");
+///
+/// assert_eq!(LINUX.arch, Architecture::X86_64 { microarch: 1 });
+/// assert_eq!(LINUX.sys.os(), Some(OS::Linux));
+/// ```
+///
+/// ```compile_fail
+/// const BAD: target_tuples::CanonicalTarget = target_tuples::target!("x86_64-pc-notanos");
+/// ```
+///
+/// ```compile_fail
+/// const BAD: target_tuples::CanonicalTarget = target_tuples::target!("x86_64-aple-darwin");
+/// ```
+#[macro_export]
+macro_rules! target {
+    ($name:literal) => {
+        $crate::__target!([$crate] $name)
+    };
+}
+
+///
+/// Parses a target at compile time, and expands to a constant [`TargetRef<'static>`][TargetRef],
+///  which keeps the exact name as written. See [`target!`]
+///
+/// ```
+/// use target_tuples::{target, target_ref, TargetRef};
+///
+/// const MUSL: TargetRef<'static> = target_ref!("x86_64-linux-musl");
+///
+/// assert_eq!(MUSL.exact(), "x86_64-linux-musl");
+/// assert_eq!(MUSL.canonical(), target!("x86_64-linux-musl"));
+/// ```
+#[macro_export]
+macro_rules! target_ref {
+    ($name:literal) => {
+        $crate::__target!([$crate] ref $name)
+    };
+}
@@ -0,0 +1,325 @@
+//! Patterns over targets, parsed at runtime
+//!
+//! A [`TargetPattern`] is written in the same syntax as the patterns of [`match_targets!`][crate::match_targets], such as
+//!  `x86_64-*-linux-*` or `*-apple-*`, and matches the same targets. This allows patterns to be read from configuration files
+//!  or command line options.
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+use crate::{pieces::*, CanonicalTarget};
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+enum ArchPattern {
+    /// `*` in the position of the architecture
+    Any,
+    /// An architecture, which must match exactly
+    Exact(Architecture),
+    /// `{family}`, which matches every architecture in the family
+    Family(&'static str),
+    /// `{arch+}`, which matches an x86 architecture at the microarchitecture level of `arch` or later
+    OrLater(Architecture),
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+enum OsPattern {
+    /// `*` in the position of the operating system
+    Any,
+    /// An operating system, which must match exactly
+    Exact(OS),
+    /// `{family}`, which matches every operating system in the family
+    Family(&'static str),
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+enum SysPattern {
+    /// `*` in the position of the system
+    Any,
+    /// A system of a single component, such as `elf` or `linux`, which must match exactly
+    System(System),
+    /// `{family}` in the position of the system, which matches any system with an operating system in the family
+    Family(&'static str),
+    /// An operating system (or `*`) followed by an environment or object format (or `*`)
+    OsEnv {
+        os: OsPattern,
+        envobj: Option<(Option<Environment>, Option<ObjectFormat>)>,
+    },
+}
+
+/// Checks whether `arch` is the same x86 architecture as `min`, at the same microarchitecture level or later
+fn is_or_later(min: Architecture, arch: Architecture) -> bool {
+    match (min, arch) {
+        (Architecture::X86_16(min), Architecture::X86_16(level))
+        | (Architecture::X86_32(min), Architecture::X86_32(level))
+        | (Architecture::X86_64 { microarch: min }, Architecture::X86_64 { microarch: level }) => {
+            level >= min
+        }
+        _ => false,
+    }
+}
+
+fn os_in_family(family: &str, os: Option<OS>) -> bool {
+    os.and_then(|os| os.family()) == Some(family)
+}
+
+///
+/// A pattern over targets, such as `x86_64-*-linux-gnu` or `*-apple-*`.
+///
+/// Patterns consist of an architecture, vendor, and system, any of which may be `*`, and have the following forms:
+/// * `*`, which matches every target,
+/// * `<arch>-*`, which matches every target with the architecture,
+/// * `<arch>-<vendor>-*`, which additionally requires the vendor,
+/// * `<arch>-<vendor>-<sys>`, where `sys` is a single component (such as `elf`) which the system of the target must equal exactly,
+/// * `<arch>-<vendor>-<os>-<env>`, where `env` is an environment or object format, or `*` to match any (including none).
+///
+/// The architecture may also be a family, such as `{x86}` or `{arm}`, or an x86 architecture followed by `+`, such as `{i686+}`,
+///  which matches the architecture at that microarchitecture level or later. The operating system (or a system of a single component)
+///  may be a family of operating systems, such as `{bsd}` or `{apple}`.
+///
+/// A vendor that is not known to the library is rejected as a likely typo, unless it is spelled `unknown`.
+/// A custom vendor may be written as a raw identifier, such as `r#mycorp`. A target does not record the name of a custom vendor,
+///  which is parsed as [`Vendor::Unknown`], so `r#mycorp` is the same as `unknown`: it matches every target with a vendor unknown to the library,
+///  such as `x86_64-othercorp-linux-gnu`, and not only `x86_64-mycorp-linux-gnu`.
+///
+/// The vendor of a pattern is compared with the vendor of the target as given by [`CanonicalTarget::guess_vendor`],
+///  so `x86_64-pc-*` matches `x86_64-linux-gnu`. Patterns match the same targets as the same pattern written in [`match_targets!`][crate::match_targets]
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct TargetPattern {
+    arch: ArchPattern,
+    vendor: Option<Vendor>,
+    sys: SysPattern,
+}
+
+impl TargetPattern {
+    /// The pattern `*`, which matches every target
+    pub const ANY: Self = Self {
+        arch: ArchPattern::Any,
+        vendor: None,
+        sys: SysPattern::Any,
+    };
+
+    /// Checks whether `targ` matches the pattern
+    pub fn matches(&self, targ: &CanonicalTarget) -> bool {
+        let arch_matches = match self.arch {
+            ArchPattern::Any => true,
+            ArchPattern::Exact(arch) => arch == targ.arch,
+            ArchPattern::Family(family) => targ.arch.family() == family,
+            ArchPattern::OrLater(min) => is_or_later(min, targ.arch),
+        };
+
+        if !arch_matches {
+            return false;
+        }
+
+        if self
+            .vendor
+            .map_or(false, |vendor| vendor != targ.guess_vendor())
+        {
+            return false;
+        }
+
+        match self.sys {
+            SysPattern::Any => true,
+            SysPattern::System(sys) => {
+                sys.os() == targ.sys.os()
+                    && sys.env() == targ.sys.env()
+                    && sys.object_format() == targ.sys.object_format()
+            }
+            SysPattern::Family(family) => os_in_family(family, targ.sys.os()),
+            SysPattern::OsEnv { os, envobj } => {
+                let os_matches = match os {
+                    OsPattern::Any => targ.sys.os().is_some(),
+                    OsPattern::Exact(os) => targ.sys.os() == Some(os),
+                    OsPattern::Family(family) => os_in_family(family, targ.sys.os()),
+                };
+
+                os_matches
+                    && envobj.map_or(true, |(env, objfmt)| {
+                        env == targ.sys.env() && objfmt == targ.sys.object_format()
+                    })
+            }
+        }
+    }
+}
+
+fn parse_component<T: FromStr>(s: &str) -> Result<Option<T>, UnknownError> {
+    if s == "*" {
+        Ok(None)
+    } else {
+        s.parse().map(Some).map_err(|_| UnknownError)
+    }
+}
+
+///
+/// Parses the vendor of a pattern, or `*`.
+///
+/// A vendor unknown to the library is rejected, unless it is spelled `unknown`, or prefixed with `r#` (such as `r#mycorp`),
+///  which matches any vendor unknown to the library
+fn parse_vendor(s: &str) -> Result<Option<Vendor>, UnknownError> {
+    if s == "*" {
+        return Ok(None);
+    }
+
+    if let Some(name) = s.strip_prefix("r#") {
+        return Ok(Some(Vendor::parse(name)));
+    }
+
+    match Vendor::parse(s) {
+        Vendor::Unknown if s != "unknown" => Err(UnknownError),
+        vendor => Ok(Some(vendor)),
+    }
+}
+
+/// Returns the name of the family written as `{name}`, if `s` is one
+fn family_name(s: &str) -> Option<&str> {
+    s.strip_prefix('{')?.strip_suffix('}')
+}
+
+fn parse_arch(s: &str) -> Result<ArchPattern, UnknownError> {
+    let Some(name) = family_name(s) else {
+        return Ok(parse_component(s)?.map_or(ArchPattern::Any, ArchPattern::Exact));
+    };
+
+    if let Some(name) = name.strip_suffix('+') {
+        let arch = name.parse::<Architecture>()?;
+        return if arch.family() == "x86" {
+            Ok(ArchPattern::OrLater(arch))
+        } else {
+            Err(UnknownError)
+        };
+    }
+
+    ARCHITECTURES
+        .iter()
+        .map(|arch| arch.family())
+        .find(|family| *family == name)
+        .map(ArchPattern::Family)
+        .ok_or(UnknownError)
+}
+
+/// Returns the family of operating systems written as `s`, or `None` if `s` is not a family
+fn parse_os_family(s: &str) -> Result<Option<&'static str>, UnknownError> {
+    let Some(name) = family_name(s) else {
+        return Ok(None);
+    };
+
+    OPERATING_SYSTEMS
+        .iter()
+        .filter_map(|os| os.family())
+        .find(|family| *family == name)
+        .map(Some)
+        .ok_or(UnknownError)
+}
+
+fn parse_os(s: &str) -> Result<OsPattern, UnknownError> {
+    match parse_os_family(s)? {
+        Some(family) => Ok(OsPattern::Family(family)),
+        None => Ok(parse_component(s)?.map_or(OsPattern::Any, OsPattern::Exact)),
+    }
+}
+
+impl FromStr for TargetPattern {
+    type Err = UnknownError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut components = s.split('-');
+
+        let first = components.next().ok_or(UnknownError)?;
+        let rest = [
+            components.next(),
+            components.next(),
+            components.next(),
+            components.next(),
+        ];
+
+        match (first, rest) {
+            ("*", [None, ..]) => Ok(Self::ANY),
+            (arch, [Some("*"), None, ..]) => Ok(Self {
+                arch: parse_arch(arch)?,
+                vendor: None,
+                sys: SysPattern::Any,
+            }),
+            (arch, [Some(vendor), Some(sys), None, ..]) => Ok(Self {
+                arch: parse_arch(arch)?,
+                vendor: parse_vendor(vendor)?,
+                sys: if sys == "*" {
+                    SysPattern::Any
+                } else if let Some(family) = parse_os_family(sys)? {
+                    SysPattern::Family(family)
+                } else {
+                    SysPattern::System(sys.parse()?)
+                },
+            }),
+            (arch, [Some(vendor), Some(os), Some(envobj), None]) => {
+                let envobj = match envobj {
+                    "*" => None,
+                    envobj => {
+                        let sys = envobj.parse::<System>()?;
+                        if sys.os().is_some() {
+                            return Err(UnknownError);
+                        }
+                        Some((sys.env(), sys.object_format()))
+                    }
+                };
+
+                Ok(Self {
+                    arch: parse_arch(arch)?,
+                    vendor: parse_vendor(vendor)?,
+                    sys: SysPattern::OsEnv {
+                        os: parse_os(os)?,
+                        envobj,
+                    },
+                })
+            }
+            _ => Err(UnknownError),
+        }
+    }
+}
+
+fn fmt_component<T: Display>(piece: Option<T>, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match piece {
+        Some(piece) => piece.fmt(f),
+        None => f.write_str("*"),
+    }
+}
+
+impl Display for TargetPattern {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        if *self == Self::ANY {
+            return f.write_str("*");
+        }
+
+        match self.arch {
+            ArchPattern::Any => f.write_str("*")?,
+            ArchPattern::Exact(arch) => arch.fmt(f)?,
+            ArchPattern::Family(family) => write!(f, "{{{}}}", family)?,
+            ArchPattern::OrLater(arch) => write!(f, "{{{}+}}", arch)?,
+        }
+        f.write_str("-")?;
+
+        if self.vendor.is_none() && self.sys == SysPattern::Any {
+            return f.write_str("*");
+        }
+
+        fmt_component(self.vendor, f)?;
+        f.write_str("-")?;
+
+        match self.sys {
+            SysPattern::Any => f.write_str("*"),
+            SysPattern::System(sys) => sys.fmt(f),
+            SysPattern::Family(family) => write!(f, "{{{}}}", family),
+            SysPattern::OsEnv { os, envobj } => {
+                match os {
+                    OsPattern::Any => f.write_str("*")?,
+                    OsPattern::Exact(os) => os.fmt(f)?,
+                    OsPattern::Family(family) => write!(f, "{{{}}}", family)?,
+                }
+                f.write_str("-")?;
+                match envobj {
+                    Some((env, objfmt)) => System::from_pieces(None, env, objfmt).fmt(f),
+                    None => f.write_str("*"),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use core::{fmt::Formatter, str::FromStr};
+
+use serde::{
+    de::{Error, Unexpected, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{CanonicalTarget, TargetRef};
+
+impl Serialize for CanonicalTarget {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for CanonicalTarget {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CanonicalVisitor;
+
+        impl<'de> Visitor<'de> for CanonicalVisitor {
+            type Value = CanonicalTarget;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                f.write_str("a target tuple")
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<CanonicalTarget, E> {
+                CanonicalTarget::from_str(v)
+                    .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_str(CanonicalVisitor)
+    }
+}
+
+impl Serialize for TargetRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.canonical().serialize(serializer)
+    }
+}
+
+/// Borrows the exact name from the input, which must therefore be a string that does not need to be unescaped
+impl<'de> Deserialize<'de> for TargetRef<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RefVisitor;
+
+        impl<'de> Visitor<'de> for RefVisitor {
+            type Value = TargetRef<'de>;
+
+            fn expecting(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                f.write_str("a borrowed target tuple")
+            }
+
+            fn visit_borrowed_str<E: Error>(self, v: &'de str) -> Result<TargetRef<'de>, E> {
+                TargetRef::try_parse(v).map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+            }
+        }
+
+        deserializer.deserialize_str(RefVisitor)
+    }
+}
+
+#[cfg(feature = "alloc")]
+mod feature_alloc {
+    use alloc::string::String;
+
+    use super::*;
+    use crate::OwnedTarget;
+
+    impl Serialize for OwnedTarget {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            (**self).serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for OwnedTarget {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            struct OwnedVisitor;
+
+            impl<'de> Visitor<'de> for OwnedVisitor {
+                type Value = OwnedTarget;
+
+                fn expecting(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                    f.write_str("a target tuple")
+                }
+
+                fn visit_str<E: Error>(self, v: &str) -> Result<OwnedTarget, E> {
+                    OwnedTarget::from_str(v)
+                        .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+                }
+
+                fn visit_string<E: Error>(self, v: String) -> Result<OwnedTarget, E> {
+                    OwnedTarget::from_owned(v).map_err(|_| E::custom("unknown target tuple"))
+                }
+            }
+
+            deserializer.deserialize_string(OwnedVisitor)
+        }
+    }
+}
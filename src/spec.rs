@@ -0,0 +1,507 @@
+//! Reading and writing rustc target-spec JSON files, which describe custom targets
+//!
+//! This module contains a small JSON reader and writer, so that the library does not depend on a JSON crate.
+//! Only the keys that describe the target tuple are interpreted. Every other key is preserved as a [`JsonValue`].
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::{Display, Formatter, Write};
+
+use crate::{cfg::TargetCfg, pieces::*, CanonicalTarget, TargetRef};
+
+///
+/// A JSON value
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    /// A number, kept as it was written
+    Number(String),
+    String(String),
+    Array(Vec<JsonValue>),
+    /// An object, with its members in the order they were written
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// Returns the string, if the value is a string
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Parses a JSON document
+    pub fn parse(s: &str) -> Result<Self, UnknownError> {
+        let mut reader = JsonReader { src: s, pos: 0 };
+        let value = reader.value()?;
+
+        reader.skip_ws();
+        if reader.pos != s.len() {
+            return Err(UnknownError);
+        }
+
+        Ok(value)
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, indent: usize) -> core::fmt::Result {
+        match self {
+            JsonValue::Null => f.write_str("null"),
+            JsonValue::Bool(b) => b.fmt(f),
+            JsonValue::Number(n) => f.write_str(n),
+            JsonValue::String(s) => write_str(f, s),
+            JsonValue::Array(values) if values.is_empty() => f.write_str("[]"),
+            JsonValue::Array(values) => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    f.write_fmt(format_args!("\n{:1$}", "", indent + 2))?;
+                    value.write(f, indent + 2)?;
+                }
+                f.write_fmt(format_args!("\n{:1$}]", "", indent))
+            }
+            JsonValue::Object(members) if members.is_empty() => f.write_str("{}"),
+            JsonValue::Object(members) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i != 0 {
+                        f.write_str(",")?;
+                    }
+                    f.write_fmt(format_args!("\n{:1$}", "", indent + 2))?;
+                    write_str(f, key)?;
+                    f.write_str(": ")?;
+                    value.write(f, indent + 2)?;
+                }
+                f.write_fmt(format_args!("\n{:1$}}}", "", indent))
+            }
+        }
+    }
+}
+
+impl Display for JsonValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+fn write_str(f: &mut Formatter<'_>, s: &str) -> core::fmt::Result {
+    f.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => f.write_fmt(format_args!("\\u{:04x}", c as u32))?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+struct JsonReader<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> JsonReader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.src[self.pos..]
+    }
+
+    fn skip_ws(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_ws();
+        if self.rest().starts_with(token) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), UnknownError> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(UnknownError)
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, UnknownError> {
+        let digits = self.rest().get(..4).ok_or(UnknownError)?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(UnknownError);
+        }
+        let val = u32::from_str_radix(digits, 16).map_err(|_| UnknownError)?;
+        self.pos += 4;
+        Ok(val)
+    }
+
+    fn string(&mut self) -> Result<String, UnknownError> {
+        self.expect("\"")?;
+        let mut s = String::new();
+
+        loop {
+            let mut chars = self.rest().chars();
+            let c = chars.next().ok_or(UnknownError)?;
+            self.pos += c.len_utf8();
+
+            match c {
+                '"' => return Ok(s),
+                '\\' => {
+                    let escape = chars.next().ok_or(UnknownError)?;
+                    self.pos += escape.len_utf8();
+                    s.push(match escape {
+                        '"' => '"',
+                        '\\' => '\\',
+                        '/' => '/',
+                        'b' => '\u{8}',
+                        'f' => '\u{c}',
+                        'n' => '\n',
+                        'r' => '\r',
+                        't' => '\t',
+                        'u' => {
+                            let mut c = self.hex4()?;
+                            if (0xD800..0xDC00).contains(&c) {
+                                self.expect("\\u")?;
+                                let low = self.hex4()?;
+                                if !(0xDC00..0xE000).contains(&low) {
+                                    return Err(UnknownError);
+                                }
+                                c = 0x10000 + ((c - 0xD800) << 10) + (low - 0xDC00);
+                            }
+                            char::from_u32(c).ok_or(UnknownError)?
+                        }
+                        _ => return Err(UnknownError),
+                    });
+                }
+                c if (c as u32) < 0x20 => return Err(UnknownError),
+                c => s.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<String, UnknownError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E')))
+            .unwrap_or(rest.len());
+        let number = &rest[..len];
+
+        if !number.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+            return Err(UnknownError);
+        }
+
+        self.pos += len;
+        Ok(number.to_string())
+    }
+
+    fn value(&mut self) -> Result<JsonValue, UnknownError> {
+        self.skip_ws();
+
+        if self.eat("null") {
+            Ok(JsonValue::Null)
+        } else if self.eat("true") {
+            Ok(JsonValue::Bool(true))
+        } else if self.eat("false") {
+            Ok(JsonValue::Bool(false))
+        } else if self.rest().starts_with('"') {
+            Ok(JsonValue::String(self.string()?))
+        } else if self.eat("[") {
+            let mut values = Vec::new();
+            if !self.eat("]") {
+                loop {
+                    values.push(self.value()?);
+                    if self.eat("]") {
+                        break;
+                    }
+                    self.expect(",")?;
+                }
+            }
+            Ok(JsonValue::Array(values))
+        } else if self.eat("{") {
+            let mut members = Vec::new();
+            if !self.eat("}") {
+                loop {
+                    self.skip_ws();
+                    let key = self.string()?;
+                    self.expect(":")?;
+                    members.push((key, self.value()?));
+                    if self.eat("}") {
+                        break;
+                    }
+                    self.expect(",")?;
+                }
+            }
+            Ok(JsonValue::Object(members))
+        } else {
+            Ok(JsonValue::Number(self.number()?))
+        }
+    }
+}
+
+/// The keys of a target spec that correspond to the `target_*` cfgs
+const CFG_KEYS: &[&str] = &[
+    "arch",
+    "vendor",
+    "os",
+    "env",
+    "abi",
+    "target-endian",
+    "target-pointer-width",
+];
+
+/// The LLVM data layouts of common targets, as used by rustc at the time of writing
+fn default_data_layout(targ: &CanonicalTarget) -> Option<&'static str> {
+    let objfmt = targ.object_format();
+
+    Some(match (targ.arch, objfmt) {
+        (Architecture::X86_64 { .. }, ObjectFormat::MachO) => {
+            "e-m:o-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128"
+        }
+        (Architecture::X86_64 { .. }, ObjectFormat::Coff) => {
+            "e-m:w-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128"
+        }
+        (Architecture::X86_64 { .. }, ObjectFormat::Elf) => {
+            "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128"
+        }
+        (Architecture::X86_32(_), ObjectFormat::Elf) => {
+            "e-m:e-p:32:32-p270:32:32-p271:32:32-p272:64:64-i128:128-f64:32:64-f80:32-n8:16:32-S128"
+        }
+        (Architecture::Aarch64, ObjectFormat::MachO) => {
+            "e-m:o-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-n32:64-S128-Fn32"
+        }
+        (Architecture::Aarch64, ObjectFormat::Elf) => {
+            "e-m:e-p270:32:32-p271:32:32-p272:64:64-i8:8:32-i16:16:32-i64:64-i128:128-n32:64-S128-Fn32"
+        }
+        (Architecture::Arm, ObjectFormat::Elf) => {
+            "e-m:e-p:32:32-Fi8-i64:64-v128:64:128-a:0:32-n32-S64"
+        }
+        (Architecture::Mips, ObjectFormat::Elf) => "E-m:m-p:32:32-i8:8:32-i16:16:32-i64:64-n32-S64",
+        (Architecture::MipsLE, ObjectFormat::Elf) => {
+            "e-m:m-p:32:32-i8:8:32-i16:16:32-i64:64-n32-S64"
+        }
+        (Architecture::RiscV32, ObjectFormat::Elf) => "e-m:e-p:32:32-i64:64-n32-S128",
+        (Architecture::RiscV64, ObjectFormat::Elf) => "e-m:e-p:64:64-i64:64-i128:128-n32:64-S128",
+        (Architecture::Wasm32, _) => {
+            "e-m:e-p:32:32-p10:8:8-p20:8:8-i64:64-i128:128-n32:64-S128-ni:1:10:20"
+        }
+        _ => return None,
+    })
+}
+
+///
+/// A rustc target-spec, as read from or written to a JSON file
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct TargetSpec {
+    /// The target described by the spec
+    pub target: CanonicalTarget,
+    /// The value of `llvm-target`
+    pub llvm_target: String,
+    /// The value of `data-layout`, if present
+    pub data_layout: Option<String>,
+    /// The other keys of the spec, in the order they were written.
+    ///
+    /// This includes keys such as `os` that correspond to a cfg, if [`Self::target`] does not produce the same value
+    ///  (for example, an operating system unknown to the library). Those keys take precedence over the target when the spec is written
+    pub properties: Vec<(String, JsonValue)>,
+}
+
+impl TargetSpec {
+    ///
+    /// Reads a target-spec from JSON.
+    ///
+    /// The target is determined from the keys rustc uses to define the `target_*` cfgs (see [`TargetCfg`]),
+    ///  refined by `llvm-target` where that names a more specific architecture (such as `i686` rather than `i586`).
+    /// Returns an error if the JSON is malformed, is not an object, or does not have an `llvm-target` string
+    pub fn parse(json: &str) -> Result<Self, UnknownError> {
+        let members = match JsonValue::parse(json)? {
+            JsonValue::Object(members) => members,
+            _ => return Err(UnknownError),
+        };
+
+        let get = |key: &str| members.iter().find(|(k, _)| k == key).map(|(_, v)| v);
+
+        let llvm_target = get("llvm-target")
+            .and_then(JsonValue::as_str)
+            .ok_or(UnknownError)?
+            .to_string();
+        let data_layout = get("data-layout")
+            .and_then(JsonValue::as_str)
+            .map(String::from);
+
+        let string = |key: &str, default: &'static str| -> String {
+            match get(key) {
+                Some(JsonValue::String(s)) => s.clone(),
+                // Newer versions of rustc write `target-pointer-width` as a number
+                Some(JsonValue::Number(n)) => n.clone(),
+                _ => default.to_string(),
+            }
+        };
+
+        let features = string("features", "");
+        let features = features
+            .split(',')
+            .filter_map(|feature| feature.strip_prefix('+'))
+            .collect::<Vec<_>>();
+
+        let (arch, vendor, os, env, abi, endian, pointer_width) = (
+            string("arch", ""),
+            string("vendor", "unknown"),
+            string("os", "none"),
+            string("env", ""),
+            string("abi", ""),
+            string("target-endian", "little"),
+            string("target-pointer-width", ""),
+        );
+
+        let cfg = TargetCfg {
+            arch: &arch,
+            vendor: &vendor,
+            os: &os,
+            env: &env,
+            abi: &abi,
+            endian: &endian,
+            pointer_width: &pointer_width,
+            features: &features,
+        };
+
+        let mut target = cfg.to_target();
+
+        if let Ok(llvm) = TargetRef::try_parse(&llvm_target) {
+            let (ours, theirs) = (target.rustc_cfgs().target, llvm.rustc_cfgs().target);
+            let more_specific = match (target.arch, llvm.arch) {
+                (Architecture::X86_32(ours), Architecture::X86_32(theirs))
+                | (
+                    Architecture::X86_64 { microarch: ours },
+                    Architecture::X86_64 { microarch: theirs },
+                ) => theirs > ours,
+                _ => true,
+            };
+            if more_specific
+                && (ours.arch, ours.endian, ours.pointer_width)
+                    == (theirs.arch, theirs.endian, theirs.pointer_width)
+            {
+                target.arch = llvm.arch;
+            }
+        }
+
+        let generated = target.rustc_cfgs().target;
+        let generated = [
+            generated.arch,
+            generated.vendor,
+            generated.os,
+            generated.env,
+            generated.abi,
+            generated.endian,
+            generated.pointer_width,
+        ];
+        let read = [&arch, &vendor, &os, &env, &abi, &endian, &pointer_width];
+
+        let properties = members
+            .iter()
+            .filter(|(key, _)| key != "llvm-target" && key != "data-layout")
+            .filter(|(key, _)| {
+                // Keep cfg keys only if the target does not reproduce them
+                match CFG_KEYS.iter().position(|k| k == key) {
+                    Some(idx) => generated[idx] != read[idx],
+                    None => true,
+                }
+            })
+            .cloned()
+            .collect();
+
+        Ok(Self {
+            target,
+            llvm_target,
+            data_layout,
+            properties,
+        })
+    }
+
+    ///
+    /// Constructs a starting target-spec for `targ`, which may be customized before it is written.
+    ///
+    /// The data layout is filled in for common architectures. For other targets, it must be supplied before rustc can use the spec
+    pub fn from_target(targ: &CanonicalTarget) -> Self {
+        let cfgs = targ.rustc_cfgs();
+
+        let max_atomic_width = ["128", "64", "32", "16", "8"]
+            .into_iter()
+            .find(|width| cfgs.has_atomic.contains(width))
+            .unwrap_or("0");
+
+        Self {
+            target: *targ,
+            llvm_target: targ.to_string(),
+            data_layout: default_data_layout(targ).map(String::from),
+            properties: Vec::from([
+                (
+                    "target-family".to_string(),
+                    JsonValue::Array(
+                        cfgs.families
+                            .iter()
+                            .map(|family| JsonValue::String(family.to_string()))
+                            .collect(),
+                    ),
+                ),
+                (
+                    "max-atomic-width".to_string(),
+                    JsonValue::Number(max_atomic_width.to_string()),
+                ),
+            ]),
+        }
+    }
+
+    /// Converts the spec into a JSON object
+    pub fn to_json(&self) -> JsonValue {
+        let cfgs = self.target.rustc_cfgs().target;
+
+        let mut members = Vec::new();
+        let mut push = |key: &str, value: JsonValue| {
+            if !self.properties.iter().any(|(k, _)| k == key) {
+                members.push((key.to_string(), value));
+            }
+        };
+
+        push("llvm-target", JsonValue::String(self.llvm_target.clone()));
+        if let Some(data_layout) = &self.data_layout {
+            push("data-layout", JsonValue::String(data_layout.clone()));
+        }
+        push("arch", JsonValue::String(cfgs.arch.to_string()));
+        push("vendor", JsonValue::String(cfgs.vendor.to_string()));
+        push("os", JsonValue::String(cfgs.os.to_string()));
+        if !cfgs.env.is_empty() {
+            push("env", JsonValue::String(cfgs.env.to_string()));
+        }
+        if !cfgs.abi.is_empty() {
+            push("abi", JsonValue::String(cfgs.abi.to_string()));
+        }
+        push("target-endian", JsonValue::String(cfgs.endian.to_string()));
+        push(
+            "target-pointer-width",
+            JsonValue::Number(cfgs.pointer_width.to_string()),
+        );
+
+        members.extend(self.properties.iter().cloned());
+
+        JsonValue::Object(members)
+    }
+}
+
+impl Display for TargetSpec {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.to_json().fmt(f)
+    }
+}
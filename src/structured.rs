@@ -0,0 +1,89 @@
+//! The structured representation of targets for serde, as a map of the pieces of the target
+//!
+//! By default, targets are serialized as their canonical name, such as `"x86_64-pc-linux-gnu"`.
+//! The structured representation is instead a map with the keys `arch`, `vendor`, `kernel`, `os`, `env`, and `objfmt`,
+//!  where absent pieces are `null`.
+//!
+//! It can be selected by wrapping the target in [`Structured`], or with `#[serde(with = "target_tuples::structured")]`
+//!  on a field of type [`CanonicalTarget`].
+
+use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{pieces::*, CanonicalTarget};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "CanonicalTarget")]
+struct Repr {
+    arch: Architecture,
+    vendor: Option<Vendor>,
+    kernel: Option<Kernel>,
+    os: Option<OS>,
+    env: Option<Environment>,
+    objfmt: Option<ObjectFormat>,
+}
+
+impl Repr {
+    fn into_target(self) -> Option<CanonicalTarget> {
+        let inferred = Kernel::infer(self.os, self.env);
+
+        let sys = match self.kernel {
+            Some(kernel) if Some(kernel) != inferred => {
+                if self.os != Some(kernel.os())
+                    || !kernel.accepts_env(self.env)
+                    || self.objfmt.is_some()
+                {
+                    return None;
+                }
+                System::from_kernel(kernel, self.env)
+            }
+            _ => {
+                if self.os.is_none() && self.env.is_none() && self.objfmt.is_none() {
+                    return None;
+                }
+                System::from_pieces(self.os, self.env, self.objfmt)
+            }
+        };
+
+        Some(CanonicalTarget::new(self.arch, self.vendor, sys))
+    }
+}
+
+/// Serializes `targ` as a map of its pieces
+pub fn serialize<S: Serializer>(targ: &CanonicalTarget, serializer: S) -> Result<S::Ok, S::Error> {
+    Repr {
+        arch: targ.arch,
+        vendor: targ.vendor,
+        kernel: targ.sys.kernel(),
+        os: targ.sys.os(),
+        env: targ.sys.env(),
+        objfmt: targ.sys.object_format(),
+    }
+    .serialize(serializer)
+}
+
+/// Deserializes a target from a map of its pieces.
+/// Fails if the pieces do not form a valid system, such as a `kernel` that does not match the `os`
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<CanonicalTarget, D::Error> {
+    Repr::deserialize(deserializer)?
+        .into_target()
+        .ok_or_else(|| D::Error::custom("the pieces of the target do not form a valid system"))
+}
+
+///
+/// A wrapper that serializes and deserializes a target with the structured representation
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Structured<T>(pub T);
+
+impl Serialize for Structured<CanonicalTarget> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Structured<CanonicalTarget> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Structured)
+    }
+}
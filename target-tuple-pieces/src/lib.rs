@@ -5,6 +5,9 @@
     clippy::match_like_matches_macro
 )] // Kill clippy for MSRV
 
+pub mod rustc;
+
+use core::cmp::Ordering;
 use core::fmt::Formatter;
 use core::{fmt::Display, str::FromStr};
 
@@ -54,6 +57,7 @@ pub enum Architecture {
     SPC700,
     Clever,
     HoleyBytes,
+    M68k,
 }
 
 impl FromStr for Architecture {
@@ -103,6 +107,7 @@ impl FromStr for Architecture {
 
             "spc700" | "spc" => Self::SPC700,
             "holeybytes" | "hbvm" | "hb" => Self::HoleyBytes,
+            "m68k" | "m68000" => Self::M68k,
 
             _ => return Err(UnknownError),
         })
@@ -128,7 +133,7 @@ impl Architecture {
     /// Returns the canonical name of the target
     /// The canonical name, when passed into `[`Self::parse`] will yield an equivalent value,
     /// Formatting an Architecture yields this string
-    pub fn canonical_name(&self) -> &'static str {
+    pub const fn canonical_name(&self) -> &'static str {
         match self {
             Architecture::Unknown => "unknown",
             Architecture::X86_16(0) => "i86",
@@ -170,14 +175,134 @@ impl Architecture {
             Architecture::SPC700 => "spc700",
             Architecture::Clever => "clever",
             Architecture::HoleyBytes => "holeybytes",
+            Architecture::M68k => "m68k",
+        }
+    }
+
+    ///
+    /// Returns the name of the family of architectures this architecture belongs to, such as `x86` for all of
+    ///  `i386`, `i686`, and `x86_64`, or `mips` for every variant of MIPS, regardless of width or endianness
+    pub const fn family(&self) -> &'static str {
+        match self {
+            Architecture::Unknown => "unknown",
+            Architecture::X86_16(_) | Architecture::X86_32(_) | Architecture::X86_64 { .. } => {
+                "x86"
+            }
+            Architecture::Arm | Architecture::ArmBe => "arm",
+            Architecture::Aarch64 | Architecture::Aarch64Be | Architecture::Aarch64_32 => "aarch64",
+            Architecture::Mips
+            | Architecture::MipsLE
+            | Architecture::Mips64
+            | Architecture::Mips64LE => "mips",
+            Architecture::PowerPC32 | Architecture::PowerPC64 | Architecture::PowerPC64le => {
+                "powerpc"
+            }
+            Architecture::RiscV32 | Architecture::RiscV64 => "riscv",
+            Architecture::Sparc | Architecture::SparcV9 | Architecture::SparcEL => "sparc",
+            Architecture::Wasm32 | Architecture::Wasm64 => "wasm",
+            Architecture::Wc65c816 | Architecture::M6502 | Architecture::M65C02 => "6502",
+            Architecture::SPC700 => "spc700",
+            Architecture::Clever => "clever",
+            Architecture::HoleyBytes => "holeybytes",
+            Architecture::M68k => "m68k",
+        }
+    }
+}
+
+macro_rules! architecture_codes {
+    (@level) => { 0 };
+    (@level $level:ident) => { $level };
+    (@zero $level:ident) => { 0 };
+    ($level:ident; $($code:literal => $variant:ident $(($tuple:ident))? $({ $field:ident: $named:ident })?),* $(,)?) => {
+        impl Architecture {
+            ///
+            /// The code of the architecture, and its microarchitecture level (or `0` for an architecture that is not x86).
+            ///
+            /// The codes follow the declaration of [`Architecture`], and are used by the packed encoding of targets in target-tuples,
+            ///  so the code of an architecture is never changed or reused
+            pub const fn code(&self) -> (u8, u8) {
+                match *self {
+                    $(Architecture::$variant $(($tuple))? $({ $field: $named })? => {
+                        ($code, architecture_codes!(@level $($tuple)? $($named)?))
+                    })*
+                }
+            }
+
+            ///
+            /// The architecture with `code` and the microarchitecture `level`, which is ignored for an architecture that is not x86.
+            /// Returns `None` if no architecture has `code`
+            pub const fn from_code(code: u8, $level: u8) -> Option<Self> {
+                match code {
+                    $($code => Some(Architecture::$variant $(($tuple))? $({ $field: $named })?),)*
+                    _ => None,
+                }
+            }
         }
+
+        /// Every architecture, with a microarchitecture level of `0` for x86 architectures
+        pub const ARCHITECTURES: &[Architecture] = &[
+            $(Architecture::$variant
+                $((architecture_codes!(@zero $tuple)))?
+                $({ $field: architecture_codes!(@zero $named) })?,)*
+        ];
+    };
+}
+
+architecture_codes! {
+    level;
+    0 => Unknown,
+    1 => X86_16(level),
+    2 => X86_32(level),
+    3 => X86_64 { microarch: level },
+    4 => Arm,
+    5 => ArmBe,
+    6 => Aarch64,
+    7 => Aarch64Be,
+    8 => Aarch64_32,
+    9 => Mips,
+    10 => MipsLE,
+    11 => Mips64,
+    12 => Mips64LE,
+    13 => PowerPC32,
+    14 => PowerPC64,
+    15 => PowerPC64le,
+    16 => RiscV32,
+    17 => RiscV64,
+    18 => Sparc,
+    19 => SparcV9,
+    20 => SparcEL,
+    21 => Wasm32,
+    22 => Wasm64,
+    23 => Wc65c816,
+    24 => M6502,
+    25 => M65C02,
+    26 => SPC700,
+    27 => Clever,
+    28 => HoleyBytes,
+    29 => M68k,
+}
+
+///
+/// Architectures are ordered by the name of their [family][Architecture::family], then by their variant,
+///  and then by their microarchitecture level, so that `i386` sorts before `i686`, which sorts before `x86_64`
+impl Ord for Architecture {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.family()
+            .cmp(other.family())
+            .then_with(|| self.code().cmp(&other.code()))
+    }
+}
+
+impl PartialOrd for Architecture {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 ///
 /// The Vendor field of a target tuple
 ///
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[non_exhaustive]
 pub enum Vendor {
     Unknown = 0,
@@ -196,6 +321,12 @@ pub enum Vendor {
     SUSE = 13,
     OpenEmbedded = 14,
     WDC = 15,
+    Sun = 16,
+    DEC = 17,
+    SGI = 18,
+    HP = 19,
+    Motorola = 20,
+    Commodore = 21,
 }
 
 impl FromStr for Vendor {
@@ -220,6 +351,12 @@ impl FromStr for Vendor {
             "suse" => Self::SUSE,
             "oe" => Self::OpenEmbedded,
             "wdc" => Self::WDC,
+            "sun" => Self::Sun,
+            "dec" => Self::DEC,
+            "sgi" => Self::SGI,
+            "hp" => Self::HP,
+            "motorola" => Self::Motorola,
+            "cbm" => Self::Commodore,
             _ => Self::Unknown,
         })
     }
@@ -243,7 +380,7 @@ impl Vendor {
     /// Returns the canonical name of the vendor
     /// The canonical name, when passed into `[`Self::parse`] will yield an equivalent value,
     /// Formatting a Vendor yields this string
-    pub fn canonical_name(&self) -> &'static str {
+    pub const fn canonical_name(&self) -> &'static str {
         match self {
             Vendor::Apple => "apple",
             Vendor::PC => "pc",
@@ -261,13 +398,19 @@ impl Vendor {
             Vendor::SUSE => "suse",
             Vendor::OpenEmbedded => "oe",
             Vendor::WDC => "wdc",
+            Vendor::Sun => "sun",
+            Vendor::DEC => "dec",
+            Vendor::SGI => "sgi",
+            Vendor::HP => "hp",
+            Vendor::Motorola => "motorola",
+            Vendor::Commodore => "cbm",
         }
     }
 }
 
 ///
 /// The Operating System Field of a target tuple
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[non_exhaustive]
 pub enum OS {
     Unknown = 0,
@@ -313,6 +456,15 @@ pub enum OS {
     CleverOS = 40,
     AbleOS = 41,
     Lilium = 42,
+    QNX = 43,
+    KNetBSD = 44,
+    KOpenSolaris = 45,
+    SunOS = 46,
+    Ultrix = 47,
+    AmigaOS = 48,
+    IRIX = 49,
+    HPUX = 50,
+    SysV = 51,
 }
 
 impl FromStr for OS {
@@ -328,6 +480,8 @@ impl FromStr for OS {
             x if x.starts_with("fuchsia") => Self::Fuchsia,
             x if x.starts_with("ios") => Self::IOS,
             x if x.starts_with("kfreebsd") => Self::KFreeBSD,
+            x if x.starts_with("knetbsd") => Self::KNetBSD,
+            x if x.starts_with("kopensolaris") => Self::KOpenSolaris,
             x if x.starts_with("linux") => Self::Linux,
             x if x.starts_with("lv2") => Self::Lv2,
             x if x.starts_with("macos") => Self::MacOSX,
@@ -360,6 +514,13 @@ impl FromStr for OS {
             x if x.starts_with("cleveros") => Self::CleverOS,
             x if x.starts_with("ableos") => Self::AbleOS,
             x if x.starts_with("lilium") => Self::Lilium,
+            x if x.starts_with("qnx") | x.starts_with("nto") => Self::QNX,
+            x if x.starts_with("sunos") => Self::SunOS,
+            x if x.starts_with("ultrix") => Self::Ultrix,
+            x if x.starts_with("amigaos") | x.starts_with("amigados") => Self::AmigaOS,
+            x if x.starts_with("irix") => Self::IRIX,
+            x if x.starts_with("hpux") => Self::HPUX,
+            x if x.starts_with("sysv") => Self::SysV,
             "none" => Self::None,
 
             _ => return Err(UnknownError),
@@ -384,7 +545,7 @@ impl OS {
     /// Returns the canonical name of the operating system
     /// The canonical name, when passed into `[`Self::parse`] will yield an equivalent value,
     /// Formatting an OS yields this string
-    pub fn canonical_name(&self) -> &'static str {
+    pub const fn canonical_name(&self) -> &'static str {
         match self {
             OS::Unknown => "unknown",
             OS::Ananas => "ananas",
@@ -428,13 +589,36 @@ impl OS {
             OS::CleverOS => "cleveros",
             OS::AbleOS => "ableos",
             OS::Lilium => "lilium",
+            OS::QNX => "qnx",
+            OS::KNetBSD => "knetbsd",
+            OS::KOpenSolaris => "kopensolaris",
+            OS::SunOS => "sunos",
+            OS::Ultrix => "ultrix",
+            OS::AmigaOS => "amigaos",
+            OS::IRIX => "irix",
+            OS::HPUX => "hpux",
+            OS::SysV => "sysv",
+        }
+    }
+
+    ///
+    /// Returns the name of the family of operating systems this operating system belongs to, if any:
+    /// * `bsd` for FreeBSD, NetBSD, OpenBSD, and DragonFly,
+    /// * `apple` for Darwin, macOS, iOS, tvOS, and watchOS,
+    /// * `solaris` for Solaris and SunOS
+    pub const fn family(&self) -> Option<&'static str> {
+        match self {
+            OS::FreeBSD | OS::NetBSD | OS::OpenBSD | OS::DragonFly => Some("bsd"),
+            OS::Darwin | OS::MacOSX | OS::IOS | OS::TvOS | OS::WatchOS => Some("apple"),
+            OS::Solaris | OS::SunOS => Some("solaris"),
+            _ => None,
         }
     }
 }
 
 ///
 /// The Environment field of target tuples
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum Environment {
     Unknown = 0,
     GNU = 1,
@@ -460,6 +644,7 @@ pub enum Environment {
 
     Standard = 23,
     Kernel = 24,
+    UClibc = 25,
 }
 
 impl FromStr for Environment {
@@ -480,6 +665,7 @@ impl FromStr for Environment {
             x if x.starts_with("musleabihf") => Self::MuslEABIHF,
             x if x.starts_with("musleabi") => Self::MuslEABI,
             x if x.starts_with("musl") => Self::Musl,
+            x if x.starts_with("uclibc") => Self::UClibc,
             x if x.starts_with("msvc") => Self::MSVC,
             x if x.starts_with("itanium") => Self::Itanium,
             x if x.starts_with("cygnus") => Self::Cygnus,
@@ -510,7 +696,7 @@ impl Environment {
     /// Returns the canonical name of the environment
     /// The canonical name, when passed into [`Self::parse`] will yield an equivalent value,
     /// Formatting an Environment yields this string
-    pub fn canonical_name(&self) -> &'static str {
+    pub const fn canonical_name(&self) -> &'static str {
         match self {
             Environment::Unknown => "unknown",
             Environment::GNU => "gnu",
@@ -534,13 +720,14 @@ impl Environment {
             Environment::MacABI => "macabi",
             Environment::Standard => "std",
             Environment::Kernel => "kernel",
+            Environment::UClibc => "uclibc",
         }
     }
 }
 
 ///
 /// The object format used by a target
-#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[non_exhaustive]
 pub enum ObjectFormat {
     Unknown = 0,
@@ -593,7 +780,7 @@ impl ObjectFormat {
     /// Returns the canonical name of the object format
     /// The canonical name, when passed into [`Self::parse`] will yield an equivalent value,
     /// Formatting an ObjectFormat yields this string
-    pub fn canonical_name(&self) -> &'static str {
+    pub const fn canonical_name(&self) -> &'static str {
         match self {
             ObjectFormat::Unknown => "unknown",
             ObjectFormat::XCoff => "xcoff",
@@ -609,8 +796,156 @@ impl ObjectFormat {
     }
 }
 
+///
+/// The kernel component of a target tuple, for systems written in GNU's `<kernel>-<os>` form
+///  (such as `linux-gnu`, `kfreebsd-gnu`, or `nto-qnx`)
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[non_exhaustive]
+pub enum Kernel {
+    Unknown = 0,
+    Linux = 1,
+    UCLinux = 2,
+    KFreeBSD = 3,
+    KNetBSD = 4,
+    KOpenSolaris = 5,
+    NetBSD = 6,
+    CloudABI = 7,
+    Nto = 8,
+}
+
+impl FromStr for Kernel {
+    type Err = UnknownError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "linux" => Self::Linux,
+            "uclinux" => Self::UCLinux,
+            x if x.starts_with("kfreebsd") => Self::KFreeBSD,
+            x if x.starts_with("knetbsd") => Self::KNetBSD,
+            x if x.starts_with("kopensolaris") => Self::KOpenSolaris,
+            x if x.starts_with("netbsd") => Self::NetBSD,
+            x if x.starts_with("cloudabi") => Self::CloudABI,
+            "nto" => Self::Nto,
+            _ => return Err(UnknownError),
+        })
+    }
+}
+
+impl Display for Kernel {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        self.canonical_name().fmt(f)
+    }
+}
+
+impl Kernel {
+    /// Parses the Kernel in a "lossy" manner
+    /// This is equivalent to [`Self::from_str`], except that [`Kernel::Unknown`] is returned, instead of an error, on an unknown kernel
+    pub fn parse(s: &str) -> Self {
+        Self::from_str(s).unwrap_or(Self::Unknown)
+    }
+
+    ///
+    /// Returns the canonical name of the kernel
+    /// The canonical name, when passed into [`Self::parse`] will yield an equivalent value,
+    /// Formatting a Kernel yields this string
+    pub const fn canonical_name(&self) -> &'static str {
+        match self {
+            Kernel::Unknown => "unknown",
+            Kernel::Linux => "linux",
+            Kernel::UCLinux => "uclinux",
+            Kernel::KFreeBSD => "kfreebsd",
+            Kernel::KNetBSD => "knetbsd",
+            Kernel::KOpenSolaris => "kopensolaris",
+            Kernel::NetBSD => "netbsd",
+            Kernel::CloudABI => "cloudabi",
+            Kernel::Nto => "nto",
+        }
+    }
+
+    ///
+    /// Returns the operating system that runs on top of this kernel
+    pub const fn os(&self) -> OS {
+        match self {
+            Kernel::Unknown => OS::Unknown,
+            Kernel::Linux | Kernel::UCLinux => OS::Linux,
+            Kernel::KFreeBSD => OS::KFreeBSD,
+            Kernel::KNetBSD => OS::KNetBSD,
+            Kernel::KOpenSolaris => OS::KOpenSolaris,
+            Kernel::NetBSD => OS::NetBSD,
+            Kernel::CloudABI => OS::CloudABI,
+            Kernel::Nto => OS::QNX,
+        }
+    }
+
+    ///
+    /// Checks whether `<kernel>-<env>` is one of the kernel-os combinations recognized by GNU config.sub.
+    /// For [`Kernel::Nto`], the os part is [`OS::QNX`] rather than an environment, and `env` must be [`None`]
+    pub const fn accepts_env(&self, env: Option<Environment>) -> bool {
+        match (self, env) {
+            (Kernel::Nto, None) => true,
+            (
+                Kernel::Linux,
+                Some(
+                    Environment::GNU
+                    | Environment::GNUABIN32
+                    | Environment::GNUABI64
+                    | Environment::GNUEABI
+                    | Environment::GNUEABIHF
+                    | Environment::GNUX32
+                    | Environment::Android
+                    | Environment::Musl
+                    | Environment::MuslEABI
+                    | Environment::MuslEABIHF
+                    | Environment::UClibc,
+                ),
+            ) => true,
+            (Kernel::UCLinux, Some(Environment::UClibc | Environment::GNU)) => true,
+            (
+                Kernel::KFreeBSD | Kernel::KNetBSD | Kernel::KOpenSolaris | Kernel::NetBSD,
+                Some(
+                    Environment::GNU
+                    | Environment::GNUABIN32
+                    | Environment::GNUABI64
+                    | Environment::GNUEABI
+                    | Environment::GNUEABIHF
+                    | Environment::GNUX32,
+                ),
+            ) => true,
+            (Kernel::NetBSD | Kernel::CloudABI, Some(Environment::EABI | Environment::EABIHF)) => {
+                true
+            }
+            _ => false,
+        }
+    }
+
+    ///
+    /// Determines the kernel of a system from its operating system and environment,
+    ///  if the pair forms one of GNU's kernel-os combinations.
+    ///
+    /// [`Kernel::UCLinux`] is never inferred, as it shares [`OS::Linux`] with [`Kernel::Linux`]
+    pub const fn infer(os: Option<OS>, env: Option<Environment>) -> Option<Kernel> {
+        let kernel = match os {
+            Some(OS::Linux) => Kernel::Linux,
+            Some(OS::KFreeBSD) => Kernel::KFreeBSD,
+            Some(OS::KNetBSD) => Kernel::KNetBSD,
+            Some(OS::KOpenSolaris) => Kernel::KOpenSolaris,
+            Some(OS::NetBSD) => Kernel::NetBSD,
+            Some(OS::CloudABI) => Kernel::CloudABI,
+            Some(OS::QNX) => return Some(Kernel::Nto),
+            _ => return None,
+        };
+
+        if kernel.accepts_env(env) {
+            Some(kernel)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
 pub struct System {
+    kernel: Option<Kernel>,
     os: Option<OS>,
     env: Option<Environment>,
     objfmt: Option<ObjectFormat>,
@@ -619,6 +954,8 @@ pub struct System {
 impl System {
     /// Converts the specified pieces into an object file. At least one must be [`Some`]
     ///
+    /// The kernel is inferred from `os` and `env`, see [`Kernel::infer`]
+    ///
     /// ## Panics
     /// Panics if all of the pieces are [`None`]
     pub const fn from_pieces(
@@ -628,11 +965,17 @@ impl System {
     ) -> Self {
         assert!(os.is_some() || env.is_some() || objfmt.is_some());
 
-        Self { os, env, objfmt }
+        Self {
+            kernel: Kernel::infer(os, env),
+            os,
+            env,
+            objfmt,
+        }
     }
 
     pub const fn from_os(os: OS) -> Self {
         Self {
+            kernel: Kernel::infer(Some(os), None),
             os: Some(os),
             env: None,
             objfmt: None,
@@ -641,14 +984,32 @@ impl System {
 
     pub const fn from_os_env(os: OS, env: Environment) -> Self {
         Self {
+            kernel: Kernel::infer(Some(os), Some(env)),
             os: Some(os),
             env: Some(env),
             objfmt: None,
         }
     }
 
+    /// Constructs a system in GNU's `<kernel>-<os>` form, such as `uclinux-uclibc` or `nto-qnx`.
+    /// The operating system is given by [`Kernel::os`]
+    ///
+    /// ## Panics
+    /// Panics if `kernel` does not accept `env`, according to [`Kernel::accepts_env`]
+    pub const fn from_kernel(kernel: Kernel, env: Option<Environment>) -> Self {
+        assert!(kernel.accepts_env(env));
+
+        Self {
+            kernel: Some(kernel),
+            os: Some(kernel.os()),
+            env,
+            objfmt: None,
+        }
+    }
+
     pub const fn from_env(env: Environment) -> Self {
         Self {
+            kernel: None,
             os: None,
             env: Some(env),
             objfmt: None,
@@ -657,12 +1018,18 @@ impl System {
 
     pub const fn from_objfmt(objfmt: ObjectFormat) -> Self {
         Self {
+            kernel: None,
             os: None,
             env: None,
             objfmt: Some(objfmt),
         }
     }
 
+    /// The kernel of the system, if it is in GNU's `<kernel>-<os>` form
+    pub const fn kernel(&self) -> Option<Kernel> {
+        self.kernel
+    }
+
     pub const fn os(&self) -> Option<OS> {
         self.os
     }
@@ -676,10 +1043,38 @@ impl System {
     }
 }
 
+///
+/// Systems are ordered by operating system, then by environment, then by object format, and finally by kernel
+impl Ord for System {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.os, self.env, self.objfmt, self.kernel).cmp(&(
+            other.os,
+            other.env,
+            other.objfmt,
+            other.kernel,
+        ))
+    }
+}
+
+impl PartialOrd for System {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
 impl core::fmt::Display for System {
     fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         let mut sep = "";
-        if let Some(os) = self.os {
+        if let Some(kernel) = self.kernel {
+            kernel.fmt(f)?;
+            sep = "-";
+            if self.env.is_none() {
+                if let Some(os) = self.os {
+                    f.write_str(sep)?;
+                    os.fmt(f)?;
+                }
+            }
+        } else if let Some(os) = self.os {
             os.fmt(f)?;
             sep = "-";
         }
@@ -704,6 +1099,21 @@ impl FromStr for System {
 
     fn from_str(sys: &str) -> Result<Self, Self::Err> {
         if let Some((os, senv)) = sys.split_once('-') {
+            if let Ok(kernel) = os.parse::<Kernel>() {
+                if senv.parse::<OS>().ok() == Some(kernel.os()) && kernel.accepts_env(None) {
+                    return Ok(Self::from_kernel(kernel, None));
+                } else if let Ok(env) = senv.parse::<Environment>() {
+                    if kernel.accepts_env(Some(env)) {
+                        return Ok(Self::from_kernel(kernel, Some(env)));
+                    }
+                }
+            }
+
+            // GNU accepts the userland and kernel swapped for Android
+            if sys == "android-linux" {
+                return Ok(Self::from_kernel(Kernel::Linux, Some(Environment::Android)));
+            }
+
             let os = os.parse::<OS>()?;
 
             let env = senv.parse::<Environment>();
@@ -711,28 +1121,224 @@ impl FromStr for System {
 
             env.map(|_| ()).or_else(|_| objfmt.map(|_| ()))?;
 
-            Ok(Self {
-                os: Some(os),
-                env: env.ok(),
-                objfmt: objfmt.ok(),
-            })
+            Ok(Self::from_pieces(Some(os), env.ok(), objfmt.ok()))
         } else if let Ok(os) = sys.parse::<OS>() {
-            Ok(Self {
-                os: Some(os),
-                env: None,
-                objfmt: None,
-            })
+            Ok(Self::from_os(os))
         } else {
             let env = sys.parse::<Environment>();
             let objfmt = sys.parse::<ObjectFormat>();
 
             env.map(|_| ()).or_else(|_| objfmt.map(|_| ()))?;
 
-            Ok(Self {
-                os: None,
-                env: env.ok(),
-                objfmt: objfmt.ok(),
-            })
+            Ok(Self::from_pieces(None, env.ok(), objfmt.ok()))
         }
     }
 }
+
+/// Every operating system
+pub const OPERATING_SYSTEMS: &[OS] = &[
+    OS::Unknown,
+    OS::Ananas,
+    OS::CloudABI,
+    OS::Darwin,
+    OS::DragonFly,
+    OS::FreeBSD,
+    OS::Fuchsia,
+    OS::IOS,
+    OS::KFreeBSD,
+    OS::Linux,
+    OS::Lv2,
+    OS::MacOSX,
+    OS::NetBSD,
+    OS::OpenBSD,
+    OS::Solaris,
+    OS::Win32,
+    OS::ZOS,
+    OS::Haiku,
+    OS::Minix,
+    OS::RTEMS,
+    OS::NaCl,
+    OS::AIX,
+    OS::CUDA,
+    OS::NVCL,
+    OS::AMDHSA,
+    OS::PS4,
+    OS::ELFIAMCU,
+    OS::TvOS,
+    OS::WatchOS,
+    OS::Mesa3D,
+    OS::Contiki,
+    OS::AMDPAL,
+    OS::HermitCore,
+    OS::Hurd,
+    OS::WASI,
+    OS::Emscripten,
+    OS::SNES,
+    OS::NES,
+    OS::None,
+    OS::CleverOS,
+    OS::AbleOS,
+    OS::Lilium,
+    OS::QNX,
+    OS::KNetBSD,
+    OS::KOpenSolaris,
+    OS::SunOS,
+    OS::Ultrix,
+    OS::AmigaOS,
+    OS::IRIX,
+    OS::HPUX,
+    OS::SysV,
+];
+
+/// Every environment
+pub const ENVIRONMENTS: &[Environment] = &[
+    Environment::Unknown,
+    Environment::GNU,
+    Environment::GNUABIN32,
+    Environment::GNUABI64,
+    Environment::GNUEABI,
+    Environment::GNUEABIHF,
+    Environment::GNUX32,
+    Environment::CODE16,
+    Environment::EABI,
+    Environment::EABIHF,
+    Environment::Android,
+    Environment::Musl,
+    Environment::MuslEABI,
+    Environment::MuslEABIHF,
+    Environment::MSVC,
+    Environment::Itanium,
+    Environment::Cygnus,
+    Environment::CoreCLR,
+    Environment::Simulator,
+    Environment::MacABI,
+    Environment::Standard,
+    Environment::Kernel,
+    Environment::UClibc,
+];
+
+///
+/// A component of a target tuple
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Component {
+    Architecture,
+    Vendor,
+    System,
+}
+
+///
+/// The error of [`parse_target`]: the component of the target that is not known to the library, and its position in the target.
+/// A target without a system has an empty `range` at its end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InvalidComponent {
+    pub component: Component,
+    pub range: core::ops::Range<usize>,
+}
+
+impl From<InvalidComponent> for UnknownError {
+    fn from(_: InvalidComponent) -> Self {
+        UnknownError
+    }
+}
+
+///
+/// Parses a target tuple into its architecture, vendor (if any), and system.
+///
+/// A target of 3 components is read as `<arch>-<sys>` if its last 2 components form a system (such as `x86_64-linux-gnu`),
+///  and as `<arch>-<vendor>-<sys>` otherwise. A vendor that is not known to the library is [`Vendor::Unknown`]
+pub fn parse_target(s: &str) -> Result<(Architecture, Option<Vendor>, System), InvalidComponent> {
+    let invalid = |component, start: usize, part: &str| InvalidComponent {
+        component,
+        range: start..(start + part.len()),
+    };
+
+    let Some((arch, rest)) = s.split_once('-') else {
+        return Err(invalid(Component::System, s.len(), ""));
+    };
+    let rest_start = arch.len() + 1;
+
+    let arch = arch
+        .parse::<Architecture>()
+        .map_err(|_| invalid(Component::Architecture, 0, arch))?;
+
+    let vendor_and_sys = |vendor: &str, sys: &str| {
+        let sys_start = rest_start + vendor.len() + 1;
+        let sys = sys
+            .parse::<System>()
+            .map_err(|_| invalid(Component::System, sys_start, sys))?;
+        Ok((Some(Vendor::parse(vendor)), sys))
+    };
+
+    let (vendor, sys) = if let Some((a, b)) = rest.split_once('-') {
+        if b.contains('-') {
+            // 4 component, this is vendor-os-env
+            vendor_and_sys(a, b)?
+        } else if let Ok(sys) = rest.parse::<System>() {
+            (None, sys)
+        } else {
+            vendor_and_sys(a, b)?
+        }
+    } else {
+        let sys = rest
+            .parse::<System>()
+            .map_err(|_| invalid(Component::System, rest_start, rest))?;
+        (None, sys)
+    };
+
+    Ok((arch, vendor, sys))
+}
+
+#[cfg(feature = "serde")]
+mod serde_impls {
+    use core::{fmt::Formatter, marker::PhantomData, str::FromStr};
+
+    use serde::{
+        de::{Error, Unexpected, Visitor},
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    use super::*;
+
+    struct FromStrVisitor<T>(PhantomData<T>);
+
+    impl<'de, T: FromStr> Visitor<'de> for FromStrVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+            f.write_str("a component of a target tuple")
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<T, E> {
+            v.parse()
+                .map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+        }
+    }
+
+    macro_rules! impl_serde_str {
+        ($($ty:ty),* $(,)?) => {
+            $(
+                impl Serialize for $ty {
+                    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                        serializer.collect_str(self)
+                    }
+                }
+
+                impl<'de> Deserialize<'de> for $ty {
+                    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                        deserializer.deserialize_str(FromStrVisitor(PhantomData))
+                    }
+                }
+            )*
+        };
+    }
+
+    impl_serde_str!(
+        Architecture,
+        Vendor,
+        OS,
+        Environment,
+        ObjectFormat,
+        Kernel,
+        System
+    );
+}
@@ -0,0 +1,128 @@
+//! The values of the `target_*` cfgs that rustc defines for each piece of a target
+//!
+//! These are shared by the conversions of `target_tuples::cfg` and the `#[cfg_target]` attribute.
+
+use crate::{Architecture, Environment, System, OS};
+
+/// The value of `target_arch` for `arch`. Architectures rustc does not support are given their canonical name
+pub fn arch(arch: Architecture) -> &'static str {
+    match arch {
+        Architecture::X86_16(_) | Architecture::X86_32(_) => "x86",
+        Architecture::X86_64 { .. } => "x86_64",
+        Architecture::Arm | Architecture::ArmBe => "arm",
+        Architecture::Aarch64 | Architecture::Aarch64Be | Architecture::Aarch64_32 => "aarch64",
+        Architecture::Mips | Architecture::MipsLE => "mips",
+        Architecture::Mips64 | Architecture::Mips64LE => "mips64",
+        Architecture::PowerPC32 => "powerpc",
+        Architecture::PowerPC64 | Architecture::PowerPC64le => "powerpc64",
+        Architecture::Sparc | Architecture::SparcEL => "sparc",
+        Architecture::SparcV9 => "sparc64",
+        arch => arch.canonical_name(),
+    }
+}
+
+/// The value of `target_os` for `sys`, which is `none` for a system without an operating system
+pub fn os(sys: System) -> &'static str {
+    match (sys.os(), sys.env()) {
+        (Some(OS::Linux), Some(Environment::Android)) => "android",
+        (Some(OS::Darwin | OS::MacOSX), _) => "macos",
+        (Some(OS::Win32), _) => "windows",
+        (Some(OS::HermitCore), _) => "hermit",
+        (Some(OS::QNX), _) => "nto",
+        (Some(os), _) => os.canonical_name(),
+        (None, _) => "none",
+    }
+}
+
+/// The values of `target_env` and `target_abi` for a target with `arch` and `sys`. A value rustc leaves unset is an empty string
+pub fn env_abi(arch: Architecture, sys: System) -> (&'static str, &'static str) {
+    let (env, abi) = match sys.env() {
+        Some(Environment::GNU | Environment::GNUABIN32) => ("gnu", ""),
+        Some(Environment::GNUEABI) => ("gnu", "eabi"),
+        Some(Environment::GNUEABIHF) => ("gnu", "eabihf"),
+        Some(Environment::GNUX32) => ("gnu", "x32"),
+        Some(Environment::GNUABI64) => ("gnu", "abi64"),
+        Some(Environment::Musl) => ("musl", ""),
+        Some(Environment::MuslEABI) => ("musl", "eabi"),
+        Some(Environment::MuslEABIHF) => ("musl", "eabihf"),
+        Some(Environment::UClibc) => ("uclibc", ""),
+        Some(Environment::MSVC) => ("msvc", ""),
+        Some(Environment::Simulator) => ("sim", "sim"),
+        Some(Environment::MacABI) => ("macabi", "macabi"),
+        Some(Environment::EABI) => ("", "eabi"),
+        Some(Environment::EABIHF) => ("", "eabihf"),
+        Some(Environment::Android) if arch == Architecture::Arm => ("", "eabi"),
+        _ => ("", ""),
+    };
+
+    let abi = match (arch, sys.os(), sys.env()) {
+        (Architecture::PowerPC64 | Architecture::PowerPC64le, Some(OS::AIX), _) => "vec-extabi",
+        // Only big-endian glibc uses the original 64-bit PowerPC ELF ABI
+        (Architecture::PowerPC64, _, Some(Environment::GNU)) => "elfv1",
+        (Architecture::PowerPC64 | Architecture::PowerPC64le, _, _) => "elfv2",
+        // musl only supports the n64 ABI on 64-bit MIPS
+        (Architecture::Mips64 | Architecture::Mips64LE, _, Some(Environment::Musl)) => "abi64",
+        _ => abi,
+    };
+
+    (env, abi)
+}
+
+/// Checks whether `target_endian` is `big` for `arch`
+pub fn is_big_endian(arch: Architecture) -> bool {
+    matches!(
+        arch,
+        Architecture::ArmBe
+            | Architecture::Aarch64Be
+            | Architecture::Mips
+            | Architecture::Mips64
+            | Architecture::PowerPC32
+            | Architecture::PowerPC64
+            | Architecture::Sparc
+            | Architecture::SparcV9
+            | Architecture::M68k
+    )
+}
+
+/// The value of `target_pointer_width` for a target with `arch` and `env`
+pub fn pointer_width(arch: Architecture, env: Option<Environment>) -> u32 {
+    match (arch, env) {
+        (Architecture::X86_64 { .. }, Some(Environment::GNUX32))
+        | (Architecture::Mips64 | Architecture::Mips64LE, Some(Environment::GNUABIN32)) => 32,
+        (
+            Architecture::X86_16(_)
+            | Architecture::Wc65c816
+            | Architecture::M6502
+            | Architecture::M65C02
+            | Architecture::SPC700,
+            _,
+        ) => 16,
+        (
+            Architecture::X86_64 { .. }
+            | Architecture::Aarch64
+            | Architecture::Aarch64Be
+            | Architecture::Mips64
+            | Architecture::Mips64LE
+            | Architecture::PowerPC64
+            | Architecture::PowerPC64le
+            | Architecture::RiscV64
+            | Architecture::SparcV9
+            | Architecture::Wasm64
+            | Architecture::Clever
+            | Architecture::HoleyBytes,
+            _,
+        ) => 64,
+        _ => 32,
+    }
+}
+
+/// The values of `target_feature` implied by `arch`, such as `avx2` for `x86_64v3`. Only the features that determine an x86 microarchitecture level are included
+pub fn baseline_features(arch: Architecture) -> &'static [&'static str] {
+    match arch {
+        Architecture::X86_64 { microarch: 4.. } => &["sse2", "sse4.2", "popcnt", "avx2", "avx512f"],
+        Architecture::X86_64 { microarch: 3 } => &["sse2", "sse4.2", "popcnt", "avx2"],
+        Architecture::X86_64 { microarch: 2 } => &["sse2", "sse4.2", "popcnt"],
+        Architecture::X86_64 { .. } | Architecture::X86_32(6..) => &["sse2"],
+        _ => &[],
+    }
+}
@@ -0,0 +1,192 @@
+/* Generated by target_tuples::capi::header. Do not edit. */
+
+#ifndef TARGET_TUPLES_H
+#define TARGET_TUPLES_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+struct target_tuple {
+    uint8_t arch;
+    uint8_t microarch;
+    uint8_t vendor;
+    uint8_t kernel;
+    uint8_t os;
+    uint8_t env;
+    uint8_t objfmt;
+};
+
+struct target_tuple_name {
+    const char *name;
+    size_t len;
+};
+
+int target_tuple_parse(const char *name, size_t len, struct target_tuple *out);
+size_t target_tuple_format(const struct target_tuple *targ, char *buf, size_t len);
+
+struct target_tuple_name target_tuple_arch_name(const struct target_tuple *targ);
+struct target_tuple_name target_tuple_vendor_name(const struct target_tuple *targ);
+struct target_tuple_name target_tuple_os_name(const struct target_tuple *targ);
+struct target_tuple_name target_tuple_env_name(const struct target_tuple *targ);
+struct target_tuple_name target_tuple_objfmt_name(const struct target_tuple *targ);
+
+#define TARGET_TUPLE_ARCH_UNKNOWN 0
+#define TARGET_TUPLE_ARCH_X86_16 1
+#define TARGET_TUPLE_ARCH_X86_32 2
+#define TARGET_TUPLE_ARCH_X86_64 3
+#define TARGET_TUPLE_ARCH_ARM 4
+#define TARGET_TUPLE_ARCH_ARMBE 5
+#define TARGET_TUPLE_ARCH_AARCH64 6
+#define TARGET_TUPLE_ARCH_AARCH64BE 7
+#define TARGET_TUPLE_ARCH_AARCH64_32 8
+#define TARGET_TUPLE_ARCH_MIPS 9
+#define TARGET_TUPLE_ARCH_MIPSLE 10
+#define TARGET_TUPLE_ARCH_MIPS64 11
+#define TARGET_TUPLE_ARCH_MIPS64LE 12
+#define TARGET_TUPLE_ARCH_POWERPC32 13
+#define TARGET_TUPLE_ARCH_POWERPC64 14
+#define TARGET_TUPLE_ARCH_POWERPC64LE 15
+#define TARGET_TUPLE_ARCH_RISCV32 16
+#define TARGET_TUPLE_ARCH_RISCV64 17
+#define TARGET_TUPLE_ARCH_SPARC 18
+#define TARGET_TUPLE_ARCH_SPARCV9 19
+#define TARGET_TUPLE_ARCH_SPARCEL 20
+#define TARGET_TUPLE_ARCH_WASM32 21
+#define TARGET_TUPLE_ARCH_WASM64 22
+#define TARGET_TUPLE_ARCH_WC65C816 23
+#define TARGET_TUPLE_ARCH_M6502 24
+#define TARGET_TUPLE_ARCH_M65C02 25
+#define TARGET_TUPLE_ARCH_SPC700 26
+#define TARGET_TUPLE_ARCH_CLEVER 27
+#define TARGET_TUPLE_ARCH_HOLEYBYTES 28
+#define TARGET_TUPLE_ARCH_M68K 29
+
+#define TARGET_TUPLE_VENDOR_UNKNOWN 1
+#define TARGET_TUPLE_VENDOR_APPLE 2
+#define TARGET_TUPLE_VENDOR_PC 3
+#define TARGET_TUPLE_VENDOR_SCEI 4
+#define TARGET_TUPLE_VENDOR_FREESCALE 5
+#define TARGET_TUPLE_VENDOR_IBM 6
+#define TARGET_TUPLE_VENDOR_IMAGINATIONTECHNOLOGIES 7
+#define TARGET_TUPLE_VENDOR_MIPSTECHNOLOGIES 8
+#define TARGET_TUPLE_VENDOR_NVIDIA 9
+#define TARGET_TUPLE_VENDOR_CSR 10
+#define TARGET_TUPLE_VENDOR_MYRIAD 11
+#define TARGET_TUPLE_VENDOR_AMD 12
+#define TARGET_TUPLE_VENDOR_MESA 13
+#define TARGET_TUPLE_VENDOR_SUSE 14
+#define TARGET_TUPLE_VENDOR_OPENEMBEDDED 15
+#define TARGET_TUPLE_VENDOR_WDC 16
+#define TARGET_TUPLE_VENDOR_SUN 17
+#define TARGET_TUPLE_VENDOR_DEC 18
+#define TARGET_TUPLE_VENDOR_SGI 19
+#define TARGET_TUPLE_VENDOR_HP 20
+#define TARGET_TUPLE_VENDOR_MOTOROLA 21
+#define TARGET_TUPLE_VENDOR_COMMODORE 22
+
+#define TARGET_TUPLE_KERNEL_UNKNOWN 1
+#define TARGET_TUPLE_KERNEL_LINUX 2
+#define TARGET_TUPLE_KERNEL_UCLINUX 3
+#define TARGET_TUPLE_KERNEL_KFREEBSD 4
+#define TARGET_TUPLE_KERNEL_KNETBSD 5
+#define TARGET_TUPLE_KERNEL_KOPENSOLARIS 6
+#define TARGET_TUPLE_KERNEL_NETBSD 7
+#define TARGET_TUPLE_KERNEL_CLOUDABI 8
+#define TARGET_TUPLE_KERNEL_NTO 9
+
+#define TARGET_TUPLE_OS_UNKNOWN 1
+#define TARGET_TUPLE_OS_ANANAS 2
+#define TARGET_TUPLE_OS_CLOUDABI 3
+#define TARGET_TUPLE_OS_DARWIN 4
+#define TARGET_TUPLE_OS_DRAGONFLY 5
+#define TARGET_TUPLE_OS_FREEBSD 6
+#define TARGET_TUPLE_OS_FUCHSIA 7
+#define TARGET_TUPLE_OS_IOS 8
+#define TARGET_TUPLE_OS_KFREEBSD 9
+#define TARGET_TUPLE_OS_LINUX 10
+#define TARGET_TUPLE_OS_LV2 11
+#define TARGET_TUPLE_OS_MACOSX 12
+#define TARGET_TUPLE_OS_NETBSD 13
+#define TARGET_TUPLE_OS_OPENBSD 14
+#define TARGET_TUPLE_OS_SOLARIS 15
+#define TARGET_TUPLE_OS_WIN32 16
+#define TARGET_TUPLE_OS_ZOS 17
+#define TARGET_TUPLE_OS_HAIKU 18
+#define TARGET_TUPLE_OS_MINIX 19
+#define TARGET_TUPLE_OS_RTEMS 20
+#define TARGET_TUPLE_OS_NACL 21
+#define TARGET_TUPLE_OS_AIX 22
+#define TARGET_TUPLE_OS_CUDA 23
+#define TARGET_TUPLE_OS_NVCL 24
+#define TARGET_TUPLE_OS_AMDHSA 25
+#define TARGET_TUPLE_OS_PS4 26
+#define TARGET_TUPLE_OS_ELFIAMCU 27
+#define TARGET_TUPLE_OS_TVOS 28
+#define TARGET_TUPLE_OS_WATCHOS 29
+#define TARGET_TUPLE_OS_MESA3D 30
+#define TARGET_TUPLE_OS_CONTIKI 31
+#define TARGET_TUPLE_OS_AMDPAL 32
+#define TARGET_TUPLE_OS_HERMITCORE 33
+#define TARGET_TUPLE_OS_HURD 34
+#define TARGET_TUPLE_OS_WASI 35
+#define TARGET_TUPLE_OS_EMSCRIPTEN 36
+#define TARGET_TUPLE_OS_SNES 38
+#define TARGET_TUPLE_OS_NES 39
+#define TARGET_TUPLE_OS_NONE 40
+#define TARGET_TUPLE_OS_CLEVEROS 41
+#define TARGET_TUPLE_OS_ABLEOS 42
+#define TARGET_TUPLE_OS_LILIUM 43
+#define TARGET_TUPLE_OS_QNX 44
+#define TARGET_TUPLE_OS_KNETBSD 45
+#define TARGET_TUPLE_OS_KOPENSOLARIS 46
+#define TARGET_TUPLE_OS_SUNOS 47
+#define TARGET_TUPLE_OS_ULTRIX 48
+#define TARGET_TUPLE_OS_AMIGAOS 49
+#define TARGET_TUPLE_OS_IRIX 50
+#define TARGET_TUPLE_OS_HPUX 51
+#define TARGET_TUPLE_OS_SYSV 52
+
+#define TARGET_TUPLE_ENV_UNKNOWN 1
+#define TARGET_TUPLE_ENV_GNU 2
+#define TARGET_TUPLE_ENV_GNUABIN32 3
+#define TARGET_TUPLE_ENV_GNUABI64 4
+#define TARGET_TUPLE_ENV_GNUEABI 5
+#define TARGET_TUPLE_ENV_GNUEABIHF 6
+#define TARGET_TUPLE_ENV_GNUX32 7
+#define TARGET_TUPLE_ENV_CODE16 8
+#define TARGET_TUPLE_ENV_EABI 9
+#define TARGET_TUPLE_ENV_EABIHF 10
+#define TARGET_TUPLE_ENV_ANDROID 11
+#define TARGET_TUPLE_ENV_MUSL 12
+#define TARGET_TUPLE_ENV_MUSLEABI 13
+#define TARGET_TUPLE_ENV_MUSLEABIHF 14
+#define TARGET_TUPLE_ENV_MSVC 16
+#define TARGET_TUPLE_ENV_ITANIUM 17
+#define TARGET_TUPLE_ENV_CYGNUS 18
+#define TARGET_TUPLE_ENV_CORECLR 19
+#define TARGET_TUPLE_ENV_SIMULATOR 20
+#define TARGET_TUPLE_ENV_MACABI 21
+#define TARGET_TUPLE_ENV_STANDARD 24
+#define TARGET_TUPLE_ENV_KERNEL 25
+#define TARGET_TUPLE_ENV_UCLIBC 26
+
+#define TARGET_TUPLE_OBJFMT_UNKNOWN 1
+#define TARGET_TUPLE_OBJFMT_XCOFF 2
+#define TARGET_TUPLE_OBJFMT_COFF 3
+#define TARGET_TUPLE_OBJFMT_ELF 4
+#define TARGET_TUPLE_OBJFMT_GOFF 5
+#define TARGET_TUPLE_OBJFMT_MACHO 6
+#define TARGET_TUPLE_OBJFMT_WASM 7
+#define TARGET_TUPLE_OBJFMT_XO65 8
+#define TARGET_TUPLE_OBJFMT_O65 9
+#define TARGET_TUPLE_OBJFMT_WLAOBJ 10
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* TARGET_TUPLES_H */
@@ -0,0 +1,5 @@
+//! Builds the C interface of target-tuples as a shared and static library. The interface is declared by `include/target_tuples.h`
+//!
+//! See [`target_tuples::capi`]
+
+pub use target_tuples::capi::*;
@@ -0,0 +1,374 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use target_tuple_pieces::{
+    rustc, Architecture, Environment, System, ARCHITECTURES, ENVIRONMENTS, OPERATING_SYSTEMS, OS,
+};
+
+use crate::{parse_vendor, Component, Frag};
+
+/// A cfg predicate, such as `all(target_arch = "x86_64", target_os = "linux")`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Pred {
+    KeyValue(&'static str, String),
+    All(Vec<Pred>),
+    Any(Vec<Pred>),
+    Not(Box<Pred>),
+}
+
+impl Pred {
+    /// The predicate that is always true
+    const TRUE: Self = Pred::All(Vec::new());
+
+    fn key_value(key: &'static str, value: &str) -> Self {
+        Pred::KeyValue(key, value.to_string())
+    }
+
+    /// `all` of `preds` without duplicates, or the only one of them
+    pub(crate) fn all(preds: impl IntoIterator<Item = Pred>) -> Self {
+        let mut all = Vec::new();
+
+        for pred in preds {
+            let inner = match pred {
+                Pred::All(inner) => inner,
+                pred => vec![pred],
+            };
+
+            for pred in inner {
+                if !all.contains(&pred) {
+                    all.push(pred);
+                }
+            }
+        }
+
+        match <[Pred; 1]>::try_from(all) {
+            Ok([pred]) => pred,
+            Err(all) => Pred::All(all),
+        }
+    }
+
+    /// `any` of `preds` without duplicates, or the only one of them
+    pub(crate) fn any(preds: impl IntoIterator<Item = Pred>) -> Self {
+        let mut any = Vec::new();
+
+        for pred in preds {
+            let inner = match pred {
+                Pred::Any(inner) => inner,
+                pred => vec![pred],
+            };
+
+            for pred in inner {
+                if pred == Self::TRUE {
+                    return Self::TRUE;
+                }
+
+                if !any.contains(&pred) {
+                    any.push(pred);
+                }
+            }
+        }
+
+        match <[Pred; 1]>::try_from(any) {
+            Ok([pred]) => pred,
+            Err(any) => Pred::Any(any),
+        }
+    }
+
+    /// Checks whether the predicate checks the cfg `key`
+    pub(crate) fn checks(&self, key: &str) -> bool {
+        match self {
+            Pred::KeyValue(k, _) => *k == key,
+            Pred::All(preds) | Pred::Any(preds) => preds.iter().any(|pred| pred.checks(key)),
+            Pred::Not(pred) => pred.checks(key),
+        }
+    }
+
+    pub(crate) fn into_tokens(self, span: Span) -> TokenStream {
+        let (name, preds) = match self {
+            Pred::KeyValue(key, value) => {
+                let mut value = Literal::string(&value);
+                value.set_span(span);
+
+                return TokenStream::from_iter([
+                    TokenTree::Ident(Ident::new(key, span)),
+                    TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+                    TokenTree::Literal(value),
+                ]);
+            }
+            Pred::All(preds) => ("all", preds),
+            Pred::Any(preds) => ("any", preds),
+            Pred::Not(pred) => ("not", vec![*pred]),
+        };
+
+        let mut inner = TokenStream::new();
+        for (i, pred) in preds.into_iter().enumerate() {
+            if i != 0 {
+                inner.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+            }
+            inner.extend(pred.into_tokens(span));
+        }
+
+        TokenStream::from_iter([
+            TokenTree::Ident(Ident::new(name, span)),
+            TokenTree::Group(Group::new(Delimiter::Parenthesis, inner)),
+        ])
+    }
+}
+
+///
+/// The cfgs of a single architecture. `target_endian` and `target_pointer_width` are only checked
+///  when they distinguish `arch` from another architecture with the same `target_arch`.
+///
+/// A level of x86_64 is checked by the target features it implies, so it also matches later levels
+fn arch_cfg(arch: Architecture) -> Pred {
+    let name = rustc::arch(arch);
+    let mut preds = vec![Pred::key_value("target_arch", name)];
+
+    let same_arch = ARCHITECTURES
+        .iter()
+        .filter(|other| rustc::arch(**other) == name);
+
+    if same_arch
+        .clone()
+        .any(|other| rustc::is_big_endian(*other) != rustc::is_big_endian(arch))
+    {
+        let endian = if rustc::is_big_endian(arch) {
+            "big"
+        } else {
+            "little"
+        };
+        preds.push(Pred::key_value("target_endian", endian));
+    }
+
+    let width = rustc::pointer_width(arch, None);
+    if same_arch
+        .clone()
+        .any(|other| rustc::pointer_width(*other, None) != width)
+    {
+        preds.push(Pred::key_value("target_pointer_width", &width.to_string()));
+    }
+
+    // The base levels are not checked, since rustc has targets without their features, such as `x86_64-unknown-uefi`
+    if let Architecture::X86_64 { microarch: 2.. } = arch {
+        preds.extend(
+            rustc::baseline_features(arch)
+                .iter()
+                .map(|feature| Pred::key_value("target_feature", feature)),
+        );
+    }
+
+    Pred::all(preds)
+}
+
+/// The architectures matched by `comp`
+fn arch_list(comp: &Component) -> Option<Vec<Architecture>> {
+    Some(match &comp.frag {
+        Frag::Ident(i, _) => vec![Architecture::from_str(i).ok()?],
+        Frag::Family {
+            name,
+            or_later: true,
+            ..
+        } => vec![Architecture::from_str(name).ok()?],
+        Frag::Family { name, .. } => ARCHITECTURES
+            .iter()
+            .copied()
+            .filter(|arch| arch.family() == name)
+            .collect(),
+        Frag::WildcardPos | Frag::WildcardRest => ARCHITECTURES.to_vec(),
+    })
+}
+
+fn arch_pred(comp: &Component) -> Option<Pred> {
+    match &comp.frag {
+        Frag::WildcardPos | Frag::WildcardRest => Some(Pred::TRUE),
+        _ => Some(Pred::any(arch_list(comp)?.into_iter().map(arch_cfg))),
+    }
+}
+
+fn vendor_pred(comp: &Component) -> Option<Pred> {
+    match &comp.frag {
+        Frag::Ident(i, span) => Some(Pred::key_value(
+            "target_vendor",
+            parse_vendor(i, comp.raw, *span).ok()?.canonical_name(),
+        )),
+        Frag::Family { .. } => None,
+        Frag::WildcardPos | Frag::WildcardRest => Some(Pred::TRUE),
+    }
+}
+
+///
+/// `target_env` and `target_abi` of a system in `systems` on one of `archs`, which all have the same environment.
+/// The ABI is checked even when it is empty, so that `linux-gnu` does not match `linux-gnueabihf`.
+/// So the predicate requires Rust 1.78, which is checked by [`cfg_target`][crate::cfg_target()].
+///
+/// An environment is left out on an architecture and system where rustc does not tell it apart from no environment,
+///  such as `eabi` on 64-bit PowerPC, whose ABI is always `elfv2`
+fn env_pred(archs: &[Architecture], systems: &[System]) -> Pred {
+    let abis = archs.iter().flat_map(|arch| {
+        systems.iter().filter_map(move |sys| {
+            let (env, abi) = rustc::env_abi(*arch, *sys);
+
+            if sys.env().is_some() && sys.os().is_some() {
+                let plain = System::from_pieces(sys.os(), None, sys.object_format());
+                if rustc::os(plain) == rustc::os(*sys) && rustc::env_abi(*arch, plain) == (env, abi)
+                {
+                    return None;
+                }
+            }
+
+            Some(Pred::key_value("target_abi", abi))
+        })
+    });
+
+    let env = systems
+        .first()
+        .map_or("", |sys| rustc::env_abi(Architecture::Unknown, *sys).0);
+
+    Pred::all([Pred::key_value("target_env", env), Pred::any(abis)])
+}
+
+/// `target_os` for each of `oses`, with any environment in `envs`
+fn os_pred(oses: &[OS], envs: &[Option<Environment>]) -> Pred {
+    Pred::any(oses.iter().flat_map(|os| {
+        envs.iter().map(|env| {
+            Pred::key_value(
+                "target_os",
+                rustc::os(System::from_pieces(Some(*os), *env, None)),
+            )
+        })
+    }))
+}
+
+/// The values of `target_os` for every operating system, with any environment in `envs`
+fn os_names(envs: &[Option<Environment>]) -> BTreeSet<&'static str> {
+    OPERATING_SYSTEMS
+        .iter()
+        .flat_map(|os| {
+            envs.iter()
+                .map(|env| rustc::os(System::from_pieces(Some(*os), *env, None)))
+        })
+        .collect()
+}
+
+fn os_family(name: &str) -> Vec<OS> {
+    OPERATING_SYSTEMS
+        .iter()
+        .copied()
+        .filter(|os| os.family() == Some(name))
+        .collect()
+}
+
+/// Every environment, and no environment
+fn any_env() -> Vec<Option<Environment>> {
+    std::iter::once(None)
+        .chain(ENVIRONMENTS.iter().copied().map(Some))
+        .collect()
+}
+
+///
+/// Returns the cfg predicate equivalent to the pattern `left`, which has already been expanded by [`pattern_tokens`][crate::pattern_tokens].
+///
+/// The object format of a target has no cfg, so it is not checked
+pub(crate) fn cfg_predicate(left: &[Component]) -> Option<Pred> {
+    let mut preds = Vec::new();
+
+    match left {
+        [Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {}
+        [arch, Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {
+            preds.push(arch_pred(arch)?);
+        }
+        [arch, vendor, Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {
+            preds.push(arch_pred(arch)?);
+            preds.push(vendor_pred(vendor)?);
+        }
+        [arch, vendor, sys] => {
+            preds.push(arch_pred(arch)?);
+            preds.push(vendor_pred(vendor)?);
+
+            match &sys.frag {
+                Frag::Ident(sys, _) => {
+                    let sys = System::from_str(sys).ok()?;
+                    preds.push(Pred::key_value("target_os", rustc::os(sys)));
+                    preds.push(env_pred(&arch_list(arch)?, &[sys]));
+                }
+                Frag::Family { name, .. } => preds.push(os_pred(&os_family(name), &any_env())),
+                Frag::WildcardPos | Frag::WildcardRest => return None,
+            }
+        }
+        [arch, vendor, os, envobj] => {
+            preds.push(arch_pred(arch)?);
+            preds.push(vendor_pred(vendor)?);
+
+            let env = match &envobj.frag {
+                Frag::Ident(i, _) => Some(System::from_str(i).ok()?.env()),
+                Frag::Family { .. } => return None,
+                Frag::WildcardPos | Frag::WildcardRest => None,
+            };
+            let envs = match env {
+                Some(env) => vec![env],
+                None => any_env(),
+            };
+
+            let oses = match &os.frag {
+                Frag::Ident(os, _) => vec![OS::from_str(os).ok()?],
+                Frag::Family { name, .. } => os_family(name),
+                Frag::WildcardPos | Frag::WildcardRest => OPERATING_SYSTEMS.to_vec(),
+            };
+
+            match &os.frag {
+                Frag::Ident(..) | Frag::Family { .. } => preds.push(os_pred(&oses, &envs)),
+                Frag::WildcardPos | Frag::WildcardRest => {
+                    // Every target has an operating system, unless an environment gives it another name (such as `android`),
+                    //  in which case only the renamed systems can have that environment
+                    if let Some(env) = env {
+                        let renamed = OPERATING_SYSTEMS
+                            .iter()
+                            .copied()
+                            .filter(|os| {
+                                rustc::os(System::from_pieces(Some(*os), env, None))
+                                    != rustc::os(System::from_os(*os))
+                            })
+                            .collect::<Vec<_>>();
+
+                        if !renamed.is_empty() {
+                            preds.push(os_pred(&renamed, &[env]));
+                        } else {
+                            // Nor can the systems renamed by another environment, which may have the same `target_env` and `target_abi`
+                            let names = os_names(&[env]);
+                            let others = os_names(&any_env())
+                                .into_iter()
+                                .filter(|name| !names.contains(name))
+                                .map(|name| Pred::key_value("target_os", name))
+                                .collect::<Vec<_>>();
+
+                            if !others.is_empty() {
+                                preds.push(Pred::Not(Box::new(Pred::any(others))));
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some(env) = env {
+                let systems = oses
+                    .iter()
+                    .map(|os| System::from_pieces(Some(*os), env, None))
+                    .collect::<Vec<_>>();
+                preds.push(env_pred(&arch_list(arch)?, &systems));
+            }
+        }
+        _ => return None,
+    }
+
+    Some(Pred::all(preds))
+}
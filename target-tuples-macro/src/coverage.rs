@@ -0,0 +1,192 @@
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use target_tuple_pieces::{
+    Architecture, Environment, ObjectFormat, System, Vendor, ARCHITECTURES, OPERATING_SYSTEMS, OS,
+};
+
+use crate::{parse_vendor, Component, Frag};
+
+/// The values of one piece of a target that a pattern matches
+#[derive(Clone, Debug)]
+pub(crate) enum Pieces<T> {
+    Any,
+    Of(BTreeSet<T>),
+}
+
+impl<T: Ord> Pieces<T> {
+    fn is_subset(&self, other: &Self) -> bool {
+        match (self, other) {
+            (_, Pieces::Any) => true,
+            (Pieces::Any, Pieces::Of(_)) => false,
+            (Pieces::Of(a), Pieces::Of(b)) => a.is_subset(b),
+        }
+    }
+
+    fn one(piece: T) -> Self {
+        Pieces::Of(BTreeSet::from([piece]))
+    }
+}
+
+/// The targets matched by a single pattern, as the values of each piece that it matches.
+///
+/// Every pattern matches each combination of the values, so one pattern covers another if each of its pieces does
+#[derive(Clone, Debug)]
+pub(crate) struct Coverage {
+    arch: Pieces<Architecture>,
+    vendor: Pieces<Vendor>,
+    os: Pieces<Option<OS>>,
+    env: Pieces<Option<Environment>>,
+    objfmt: Pieces<Option<ObjectFormat>>,
+}
+
+impl Coverage {
+    const ANY: Self = Self {
+        arch: Pieces::Any,
+        vendor: Pieces::Any,
+        os: Pieces::Any,
+        env: Pieces::Any,
+        objfmt: Pieces::Any,
+    };
+
+    /// Checks whether every target matched by `self` is also matched by `other`
+    pub(crate) fn is_covered_by(&self, other: &Self) -> bool {
+        self.arch.is_subset(&other.arch)
+            && self.vendor.is_subset(&other.vendor)
+            && self.os.is_subset(&other.os)
+            && self.env.is_subset(&other.env)
+            && self.objfmt.is_subset(&other.objfmt)
+    }
+
+    /// Checks whether the pattern matches every target
+    pub(crate) fn is_everything(&self) -> bool {
+        Self::ANY.is_covered_by(self)
+    }
+}
+
+/// Every x86 architecture of the same kind as `arch`, at the microarchitecture level `min` or later
+fn levels(arch: Architecture, min: u8) -> Vec<Architecture> {
+    (min..=u8::MAX)
+        .map(|level| match arch {
+            Architecture::X86_16(_) => Architecture::X86_16(level),
+            Architecture::X86_32(_) => Architecture::X86_32(level),
+            Architecture::X86_64 { .. } => Architecture::X86_64 { microarch: level },
+            arch => arch,
+        })
+        .collect()
+}
+
+fn arch_coverage(comp: &Component) -> Option<Pieces<Architecture>> {
+    Some(match &comp.frag {
+        Frag::Ident(i, _) => Pieces::one(Architecture::from_str(i).ok()?),
+        Frag::Family {
+            name,
+            or_later: true,
+            ..
+        } => {
+            let arch = Architecture::from_str(name).ok()?;
+            let min = match arch {
+                Architecture::X86_16(level)
+                | Architecture::X86_32(level)
+                | Architecture::X86_64 { microarch: level } => level,
+                _ => return None,
+            };
+
+            Pieces::Of(levels(arch, min).into_iter().collect())
+        }
+        Frag::Family { name, .. } => Pieces::Of(
+            ARCHITECTURES
+                .iter()
+                .filter(|arch| arch.family() == name)
+                .flat_map(|arch| levels(*arch, 0))
+                .collect(),
+        ),
+        Frag::WildcardPos | Frag::WildcardRest => Pieces::Any,
+    })
+}
+
+fn vendor_coverage(comp: &Component) -> Option<Pieces<Vendor>> {
+    match &comp.frag {
+        Frag::Ident(i, span) => Some(Pieces::one(parse_vendor(i, comp.raw, *span).ok()?)),
+        Frag::Family { .. } => None,
+        Frag::WildcardPos | Frag::WildcardRest => Some(Pieces::Any),
+    }
+}
+
+fn os_family(name: &str) -> Pieces<Option<OS>> {
+    Pieces::Of(
+        OPERATING_SYSTEMS
+            .iter()
+            .filter(|os| os.family() == Some(name))
+            .map(|os| Some(*os))
+            .collect(),
+    )
+}
+
+///
+/// Returns the targets matched by the pattern `left`, which has already been expanded by [`pattern_tokens`][crate::pattern_tokens].
+///
+/// Returns `None` if the pattern is not valid, in which case its expansion has already reported an error
+pub(crate) fn coverage(left: &[Component]) -> Option<Coverage> {
+    let mut cov = Coverage::ANY;
+
+    match left {
+        [Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {}
+        [arch, Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {
+            cov.arch = arch_coverage(arch)?;
+        }
+        [arch, vendor, Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {
+            cov.arch = arch_coverage(arch)?;
+            cov.vendor = vendor_coverage(vendor)?;
+        }
+        [arch, vendor, sys] => {
+            cov.arch = arch_coverage(arch)?;
+            cov.vendor = vendor_coverage(vendor)?;
+
+            match &sys.frag {
+                Frag::Ident(sys, _) => {
+                    let sys = System::from_str(sys).ok()?;
+                    cov.os = Pieces::one(sys.os());
+                    cov.env = Pieces::one(sys.env());
+                    cov.objfmt = Pieces::one(sys.object_format());
+                }
+                Frag::Family { name, .. } => cov.os = os_family(name),
+                Frag::WildcardPos | Frag::WildcardRest => return None,
+            }
+        }
+        [arch, vendor, os, envobj] => {
+            cov.arch = arch_coverage(arch)?;
+            cov.vendor = vendor_coverage(vendor)?;
+
+            cov.os = match &os.frag {
+                Frag::Ident(os, _) => Pieces::one(Some(OS::from_str(os).ok()?)),
+                Frag::Family { name, .. } => os_family(name),
+                Frag::WildcardPos | Frag::WildcardRest => {
+                    Pieces::Of(OPERATING_SYSTEMS.iter().map(|os| Some(*os)).collect())
+                }
+            };
+
+            match &envobj.frag {
+                Frag::Ident(i, _) => {
+                    let sys = System::from_str(i).ok()?;
+                    cov.env = Pieces::one(sys.env());
+                    cov.objfmt = Pieces::one(sys.object_format());
+                }
+                Frag::Family { .. } => return None,
+                Frag::WildcardPos | Frag::WildcardRest => {}
+            }
+        }
+        _ => return None,
+    }
+
+    Some(cov)
+}
@@ -1,12 +1,12 @@
 use proc_macro::{Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
-use target_tuple_pieces::{Architecture, Environment, ObjectFormat, Vendor, OS};
+use target_tuple_pieces::{Architecture, Environment, Kernel, ObjectFormat, Vendor, OS};
 
 use crate::emit_path;
 
 pub struct Wildcard;
 
 impl AsConstructor for Wildcard {
-    fn into_ctor(&self, span: Span, _dcrate: &TokenStream) -> TokenStream {
+    fn to_ctor(&self, span: Span, _dcrate: &TokenStream) -> TokenStream {
         [TokenTree::Ident(Ident::new("_", span))]
             .into_iter()
             .collect()
@@ -14,18 +14,18 @@ impl AsConstructor for Wildcard {
 }
 
 pub trait AsConstructor {
-    fn into_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream;
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream;
 }
 
 impl<T: AsConstructor> AsConstructor for Option<T> {
-    fn into_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
         match self {
             Some(v) => {
                 let mut base: TokenStream =
                     emit_path(dcrate, ["__core", "option", "Option", "Some"], span).collect();
                 base.extend([TokenTree::Group(Group::new(
                     proc_macro::Delimiter::Parenthesis,
-                    v.into_ctor(span, dcrate),
+                    v.to_ctor(span, dcrate),
                 ))]);
                 base
             }
@@ -35,7 +35,7 @@ impl<T: AsConstructor> AsConstructor for Option<T> {
 }
 
 impl AsConstructor for Architecture {
-    fn into_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
         let (name, tail) = match self {
             Self::X86_16(g) => (
                 "X86_16",
@@ -94,6 +94,7 @@ impl AsConstructor for Architecture {
             Architecture::SPC700 => ("SPC700", TokenStream::new()),
             Architecture::Clever => ("Clever", TokenStream::new()),
             Architecture::HoleyBytes => ("HoleyBytes", TokenStream::new()),
+            Architecture::M68k => ("M68k", TokenStream::new()),
             _ => unimplemented!("Version Mismatch between target-tuples-macro and target-tuples"),
         };
 
@@ -106,7 +107,7 @@ impl AsConstructor for Architecture {
 }
 
 impl AsConstructor for Vendor {
-    fn into_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
         let name = format!("{self:?}");
 
         emit_path(dcrate, ["pieces", "Vendor", &name], span).collect()
@@ -114,7 +115,7 @@ impl AsConstructor for Vendor {
 }
 
 impl AsConstructor for OS {
-    fn into_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
         let name = format!("{self:?}");
 
         emit_path(dcrate, ["pieces", "OS", &name], span).collect()
@@ -122,7 +123,7 @@ impl AsConstructor for OS {
 }
 
 impl AsConstructor for Environment {
-    fn into_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
         let name = format!("{self:?}");
 
         emit_path(dcrate, ["pieces", "Environment", &name], span).collect()
@@ -130,9 +131,107 @@ impl AsConstructor for Environment {
 }
 
 impl AsConstructor for ObjectFormat {
-    fn into_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
         let name = format!("{self:?}");
 
         emit_path(dcrate, ["pieces", "ObjectFormat", &name], span).collect()
     }
 }
+
+impl AsConstructor for Kernel {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+        let name = format!("{self:?}");
+
+        emit_path(dcrate, ["pieces", "Kernel", &name], span).collect()
+    }
+}
+
+/// Constructs the pattern of an x86 architecture, with `level` as the pattern of its microarchitecture level.
+/// Returns [`None`] for other architectures
+fn x86_level_ctor(
+    arch: &Architecture,
+    level: TokenStream,
+    span: Span,
+    dcrate: &TokenStream,
+) -> Option<TokenStream> {
+    let (name, tail) = match arch {
+        Architecture::X86_16(_) => (
+            "X86_16",
+            TokenTree::Group(Group::new(proc_macro::Delimiter::Parenthesis, level)),
+        ),
+        Architecture::X86_32(_) => (
+            "X86_32",
+            TokenTree::Group(Group::new(proc_macro::Delimiter::Parenthesis, level)),
+        ),
+        Architecture::X86_64 { .. } => {
+            let mut fields = TokenStream::from_iter([
+                TokenTree::Ident(Ident::new_raw("microarch", Span::call_site())),
+                TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+            ]);
+            fields.extend(level);
+            (
+                "X86_64",
+                TokenTree::Group(Group::new(proc_macro::Delimiter::Brace, fields)),
+            )
+        }
+        _ => return None,
+    };
+
+    let mut base =
+        emit_path(dcrate, ["pieces", "Architecture", name], span).collect::<TokenStream>();
+    base.extend([tail]);
+
+    Some(base)
+}
+
+/// An architecture pattern which matches every microarchitecture level of x86 architectures
+pub struct AnyLevel(pub Architecture);
+
+impl AsConstructor for AnyLevel {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+        let level = Wildcard.to_ctor(span, dcrate);
+
+        x86_level_ctor(&self.0, level, span, dcrate).unwrap_or_else(|| self.0.to_ctor(span, dcrate))
+    }
+}
+
+/// An architecture pattern which matches the microarchitecture level of an x86 architecture, or any later level
+pub struct OrLater(pub Architecture);
+
+impl AsConstructor for OrLater {
+    fn to_ctor(&self, span: Span, dcrate: &TokenStream) -> TokenStream {
+        let min = match self.0 {
+            Architecture::X86_16(level)
+            | Architecture::X86_32(level)
+            | Architecture::X86_64 { microarch: level } => level,
+            _ => return self.0.to_ctor(span, dcrate),
+        };
+
+        let level = TokenStream::from_iter([
+            TokenTree::Literal(Literal::u8_suffixed(min)),
+            TokenTree::Punct(Punct::new('.', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('.', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+            TokenTree::Literal(Literal::u8_suffixed(u8::MAX)),
+        ]);
+
+        x86_level_ctor(&self.0, level, span, dcrate).unwrap()
+    }
+}
+
+/// Combines `pats` into a single pattern with `|`
+pub fn alternatives(pats: impl IntoIterator<Item = TokenStream>) -> TokenStream {
+    let mut inner = TokenStream::new();
+
+    for pat in pats {
+        if !inner.is_empty() {
+            inner.extend([TokenTree::Punct(Punct::new('|', Spacing::Alone))]);
+        }
+        inner.extend(pat);
+    }
+
+    TokenStream::from_iter([TokenTree::Group(Group::new(
+        proc_macro::Delimiter::Parenthesis,
+        inner,
+    ))])
+}
@@ -3,8 +3,12 @@ use std::{iter::Peekable, str::FromStr};
 use proc_macro::*;
 use target_tuple_pieces::*;
 
-use crate::helpers::{AsConstructor, Wildcard};
+use crate::cfg_target::{cfg_predicate, Pred};
+use crate::coverage::{coverage, Coverage};
+use crate::helpers::{alternatives, AnyLevel, AsConstructor, OrLater, Wildcard};
 
+mod cfg_target;
+mod coverage;
 mod helpers;
 
 struct Error {
@@ -13,6 +17,15 @@ struct Error {
 }
 
 fn emit_error(err: Error) -> TokenStream {
+    let mut msg = Literal::string(&err.msg);
+    msg.set_span(err.span);
+
+    let mut group = Group::new(
+        Delimiter::Parenthesis,
+        [TokenTree::Literal(msg)].into_iter().collect(),
+    );
+    group.set_span(err.span);
+
     [
         TokenTree::Punct(Punct::new(':', Spacing::Joint)),
         TokenTree::Punct(Punct::new(':', Spacing::Alone)),
@@ -21,17 +34,67 @@ fn emit_error(err: Error) -> TokenStream {
         TokenTree::Punct(Punct::new(':', Spacing::Alone)),
         TokenTree::Ident(Ident::new_raw("compile_error", err.span)),
         TokenTree::Punct(Punct::new('!', Spacing::Alone)),
-        TokenTree::Group(Group::new(
-            Delimiter::Parenthesis,
-            [TokenTree::Literal(Literal::string(&err.msg))]
-                .into_iter()
-                .collect(),
-        )),
+        TokenTree::Group(group),
     ]
     .into_iter()
+    .map(|mut tt| {
+        tt.set_span(err.span);
+        tt
+    })
     .collect()
 }
 
+///
+/// Emits `msg` as a warning at `span`.
+///
+/// Procedural macros cannot emit warnings on stable, so the warning is the use of a deprecated constant, with `msg` as the note.
+/// Like any deprecation, it can be silenced with `#[allow(deprecated)]`
+fn emit_warning(msg: &str, span: Span) -> TokenStream {
+    let name = Ident::new("match_targets", span.resolved_at(Span::mixed_site()));
+
+    let mut note = TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("note", span)),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+    ]);
+    note.extend([TokenTree::Literal(Literal::string(msg))]);
+
+    let attr = |inner: TokenStream| {
+        [
+            TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+            TokenTree::Group(Group::new(Delimiter::Bracket, inner)),
+        ]
+    };
+
+    let mut block = TokenStream::new();
+    block.extend(attr(TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("deprecated", span)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, note)),
+    ])));
+    block.extend(attr(TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("allow", span)),
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([TokenTree::Ident(Ident::new("non_upper_case_globals", span))]),
+        )),
+    ])));
+    block.extend([
+        TokenTree::Ident(Ident::new("const", span)),
+        TokenTree::Ident(name.clone()),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenStream::new())),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenStream::new())),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("let", span)),
+        TokenTree::Ident(Ident::new("_", span)),
+        TokenTree::Punct(Punct::new('=', Spacing::Alone)),
+        TokenTree::Ident(name),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]);
+
+    TokenStream::from_iter([TokenTree::Group(Group::new(Delimiter::Brace, block))])
+}
+
 fn emit_path<'a>(
     dollar_crate: &TokenStream,
     components: impl IntoIterator<Item = &'a str>,
@@ -64,8 +127,40 @@ pub fn __match_targets(ts: TokenStream) -> TokenStream {
 
 fn impl_match_targets(
     dollar_crate: &TokenStream,
-    mut iter: impl Iterator<Item = TokenTree>,
+    iter: impl Iterator<Item = TokenTree>,
 ) -> Result<TokenStream, Error> {
+    let mut iter = iter.peekable();
+
+    let mut require_wildcard = false;
+
+    while let Some(TokenTree::Punct(p)) = iter.peek() {
+        if p.as_char() != '#' {
+            break;
+        }
+        let hash = p.span();
+        iter.next();
+
+        let opt = match iter.next() {
+            Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Bracket => g,
+            _ => {
+                return Err(Error {
+                    span: hash,
+                    msg: "Expected an option in `[]` after `#`".to_string(),
+                })
+            }
+        };
+
+        match opt.stream().to_string().as_str() {
+            "require_wildcard" => require_wildcard = true,
+            name => {
+                return Err(Error {
+                    span: opt.span(),
+                    msg: format!("Unknown option {name}"),
+                })
+            }
+        }
+    }
+
     let mut expr = Vec::new();
 
     let match_body = loop {
@@ -75,7 +170,7 @@ fn impl_match_targets(
             None => {
                 return Err(Error {
                     span: Span::call_site(),
-                    msg: format!("Unexpected EOF"),
+                    msg: "Unexpected EOF".to_string(),
                 })
             }
         }
@@ -93,11 +188,82 @@ fn impl_match_targets(
 
     let mut iter = iter.peekable();
 
-    while let Some(v) = parse_match_arm(&mut iter, dollar_crate)? {
-        arms.extend(v);
+    // The patterns of the arms without guards, which shadow any later pattern they cover
+    let mut covered = Vec::<Coverage>::new();
+    let mut warnings = TokenStream::new();
+    let mut last = None;
+    let mut unparsed = None;
+
+    loop {
+        if let Some(TokenTree::Punct(p)) = iter.peek() {
+            if p.as_char() == '!' {
+                let bang = p.span();
+                iter.next();
+
+                if unparsed.is_some() {
+                    return Err(Error {
+                        span: bang,
+                        msg: "Expected at most one `!unparsed` arm".to_string(),
+                    });
+                }
+                unparsed = Some(parse_unparsed_arm(&mut iter, bang)?);
+                continue;
+            }
+        }
+
+        let Some(arm) = parse_match_arm(&mut iter, dollar_crate)? else {
+            break;
+        };
+
+        let shadowed = |cov: &Option<Coverage>, earlier: &[Coverage]| match cov {
+            Some(cov) => earlier.iter().any(|e| cov.is_covered_by(e)),
+            None => false,
+        };
+
+        if arm.patterns.iter().all(|(cov, _)| shadowed(cov, &covered)) {
+            warnings.extend(emit_warning(
+                "This arm is unreachable, as every target it matches is matched by an earlier arm",
+                arm.patterns[0].1,
+            ));
+        } else {
+            let mut earlier = covered.clone();
+            for (cov, span) in &arm.patterns {
+                if shadowed(cov, &earlier) {
+                    warnings.extend(emit_warning(
+                        "This pattern is unreachable, as every target it matches is matched by an earlier pattern",
+                        *span,
+                    ));
+                }
+                earlier.extend(cov.clone());
+            }
+        }
+
+        if !arm.guarded {
+            covered.extend(arm.patterns.iter().filter_map(|(cov, _)| cov.clone()));
+        }
+
+        arms.extend(arm.tokens);
+        last = Some((arm.patterns, arm.guarded));
     }
 
-    let mut inner = TokenStream::new();
+    if require_wildcard {
+        let ends_with_wildcard = match &last {
+            Some((patterns, false)) => patterns
+                .iter()
+                .any(|(cov, _)| matches!(cov, Some(cov) if cov.is_everything())),
+            _ => false,
+        };
+
+        if !ends_with_wildcard {
+            let span = last.map_or(Span::call_site(), |(patterns, _)| patterns[0].1);
+            return Err(Error {
+                span,
+                msg: "The last arm must match every target with `*` and no guard, as required by `#[require_wildcard]`".to_string(),
+            });
+        }
+    }
+
+    let mut inner = warnings;
 
     let mut fields = TokenStream::new();
 
@@ -134,25 +300,104 @@ fn impl_match_targets(
         TokenTree::Punct(Punct::new(',', Spacing::Alone)),
     ]);
 
-    inner.extend([
+    // let __targ_name: &CanonicalTarget = &__targ; match (...) { arms }
+    let mut parsed = TokenStream::new();
+    parsed.extend([
         TokenTree::Ident(Ident::new("let", var_span)),
         TokenTree::Ident(Ident::new_raw("__targ_name", var_span)),
         TokenTree::Punct(Punct::new(':', Spacing::Alone)),
         TokenTree::Punct(Punct::new('&', Spacing::Alone)),
     ]);
-    inner.extend(emit_path(dollar_crate, ["CanonicalTarget"], var_span));
-    inner.extend([
+    parsed.extend(emit_path(dollar_crate, ["CanonicalTarget"], var_span));
+    parsed.extend([
         TokenTree::Punct(Punct::new('=', Spacing::Alone)),
         TokenTree::Punct(Punct::new('&', Spacing::Alone)),
-    ]);
-    inner.extend(expr);
-    inner.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
-    inner.extend([
+        TokenTree::Ident(Ident::new_raw("__targ", var_span)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
         TokenTree::Ident(Ident::new("match", var_span)),
         TokenTree::Group(Group::new(Delimiter::Parenthesis, fields)),
         TokenTree::Group(Group::new(Delimiter::Brace, arms.into_iter().collect())),
     ]);
 
+    // { #[allow(unused_imports)] use $crate::__MatchTarget as _; (expr).__match_target() }
+    let mut scrutinee = TokenStream::from_iter([
+        TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+        TokenTree::Group(Group::new(
+            Delimiter::Bracket,
+            TokenStream::from_iter([
+                TokenTree::Ident(Ident::new("allow", var_span)),
+                TokenTree::Group(Group::new(
+                    Delimiter::Parenthesis,
+                    TokenStream::from_iter([TokenTree::Ident(Ident::new(
+                        "unused_imports",
+                        var_span,
+                    ))]),
+                )),
+            ]),
+        )),
+        TokenTree::Ident(Ident::new("use", var_span)),
+    ]);
+    scrutinee.extend(emit_path(dollar_crate, ["__MatchTarget"], var_span));
+    scrutinee.extend([
+        TokenTree::Ident(Ident::new("as", var_span)),
+        TokenTree::Ident(Ident::new("_", var_span)),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, expr)),
+        TokenTree::Punct(Punct::new('.', Spacing::Alone)),
+        TokenTree::Ident(Ident::new_raw("__match_target", expr_span)),
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, TokenStream::new())),
+    ]);
+
+    // Without an `!unparsed` arm, the error must be `Infallible`, which is only the case for targets that are already parsed
+    let (err_pat, err_body) = unparsed.unwrap_or_else(|| {
+        let err = TokenTree::Ident(Ident::new_raw("__err", var_span));
+        let mut body: TokenStream =
+            emit_path(dollar_crate, ["__unparsed_arm_required"], expr_span).collect();
+        body.extend([TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([err.clone()]),
+        ))]);
+
+        (
+            TokenStream::from_iter([err]),
+            TokenStream::from_iter([TokenTree::Group(Group::new(Delimiter::Brace, body))]),
+        )
+    });
+
+    let mut results = TokenStream::new();
+    results.extend(emit_path(
+        dollar_crate,
+        ["__core", "result", "Result", "Ok"],
+        var_span,
+    ));
+    results.extend([
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenStream::from_iter([TokenTree::Ident(Ident::new_raw("__targ", var_span))]),
+        )),
+        TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+        TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+        TokenTree::Group(Group::new(Delimiter::Brace, parsed)),
+    ]);
+    results.extend(emit_path(
+        dollar_crate,
+        ["__core", "result", "Result", "Err"],
+        var_span,
+    ));
+    results.extend([
+        TokenTree::Group(Group::new(Delimiter::Parenthesis, err_pat)),
+        TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+        TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+    ]);
+    results.extend(err_body);
+    results.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+
+    inner.extend([
+        TokenTree::Ident(Ident::new("match", var_span)),
+        TokenTree::Group(Group::new(Delimiter::Brace, scrutinee)),
+        TokenTree::Group(Group::new(Delimiter::Brace, results)),
+    ]);
+
     Ok(inner)
 }
 
@@ -161,6 +406,21 @@ enum Frag {
     WildcardRest,
     WildcardPos,
     Ident(String, Span),
+    /// `{family}`, or `{arch+}` for an x86 architecture at a microarchitecture level or later
+    Family {
+        name: String,
+        or_later: bool,
+        span: Span,
+    },
+}
+
+/// A component of a pattern, along with the name it is bound to by `name @ component`
+#[derive(Clone, Debug)]
+struct Component {
+    frag: Frag,
+    binding: Option<Ident>,
+    /// Whether the component is a raw identifier, such as `r#mycorp`
+    raw: bool,
 }
 
 fn try_into_frag(tt: TokenTree, has_tail: bool) -> Result<Frag, Error> {
@@ -189,89 +449,645 @@ fn try_into_frag(tt: TokenTree, has_tail: bool) -> Result<Frag, Error> {
                 Ok(Frag::Ident(st, span))
             }
         }
+        TokenTree::Group(g) if g.delimiter() == Delimiter::Brace => {
+            let mut inner = g.stream().into_iter();
+
+            let name = match inner.next() {
+                Some(TokenTree::Ident(id)) => id.to_string(),
+                Some(TokenTree::Literal(lit)) => lit.to_string(),
+                _ => {
+                    return Err(Error {
+                        span: g.span(),
+                        msg: format!("Expected the name of a family, got `{g}`"),
+                    })
+                }
+            };
+
+            let or_later = match inner.next() {
+                None => false,
+                Some(TokenTree::Punct(p)) if p.as_char() == '+' => true,
+                Some(tt) => {
+                    return Err(Error {
+                        span: tt.span(),
+                        msg: format!("Expected `+` or `}}`, got `{tt}`"),
+                    })
+                }
+            };
+
+            if let Some(tt) = inner.next() {
+                return Err(Error {
+                    span: tt.span(),
+                    msg: format!("Expected `}}`, got `{tt}`"),
+                });
+            }
+
+            Ok(Frag::Family {
+                name,
+                or_later,
+                span: g.span(),
+            })
+        }
         tt => Err(Error {
             span: tt.span(),
-            msg: format!("Expected `*` or identifier, got `{tt}`"),
+            msg: format!("Expected `*`, identifier, or family, got `{tt}`"),
         }),
     }
 }
 
-#[allow(irrefutable_let_patterns)]
-fn parse_match_arm(
+/// The operating systems in the family `name`
+fn os_family(name: &str, span: Span, dcrate: &TokenStream) -> Result<TokenStream, Error> {
+    let members = OPERATING_SYSTEMS
+        .iter()
+        .filter(|os| os.family() == Some(name))
+        .map(|os| os.to_ctor(span, dcrate))
+        .collect::<Vec<_>>();
+
+    if members.is_empty() {
+        return Err(Error {
+            span,
+            msg: format!("Unknown operating system family {name}"),
+        });
+    }
+
+    Ok(alternatives(members))
+}
+
+fn unsupported_family(comp: &Component, what: &str) -> Result<(), Error> {
+    match &comp.frag {
+        Frag::Family { span, .. } => Err(Error {
+            span: *span,
+            msg: format!("Families of {what} are not supported"),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// The token that ends a pattern in a match arm
+enum PatternEnd {
+    /// `|`, which is followed by another alternative
+    Or,
+    /// `if`, which is followed by a guard and then `=>`
+    Guard(Ident),
+    /// `=>`, which is followed by the body of the arm
+    Arrow,
+}
+
+/// Parses the `|`, `if`, or `=>` that ends a pattern. `expected` describes the tokens accepted at this point, for errors
+fn parse_pattern_end(
     iter: &mut Peekable<impl Iterator<Item = TokenTree>>,
-    dcrate: &TokenStream,
-) -> Result<Option<TokenStream>, Error> {
-    let Some(_) = iter.peek() else {
-        return Ok(None);
-    };
+    prev: Span,
+    expected: &str,
+) -> Result<PatternEnd, Error> {
+    match iter.next() {
+        Some(TokenTree::Punct(p)) if p.as_char() == '=' && p.spacing() == Spacing::Joint => {
+            match iter.next() {
+                Some(TokenTree::Punct(p)) if p.as_char() == '>' => Ok(PatternEnd::Arrow),
+                Some(p) => Err(Error {
+                    span: p.span(),
+                    msg: format!("Expected `=>`, got `{p}`"),
+                }),
+                None => Err(Error {
+                    span: p.span(),
+                    msg: "Expected `=>`, got unexpected EOF".to_string(),
+                }),
+            }
+        }
+        Some(TokenTree::Punct(p)) if p.as_char() == '|' => Ok(PatternEnd::Or),
+        Some(TokenTree::Ident(id)) if id.to_string() == "if" => Ok(PatternEnd::Guard(id)),
+        Some(tt) => Err(Error {
+            span: tt.span(),
+            msg: format!("Expected {expected}, got `{tt}`"),
+        }),
+        None => Err(Error {
+            span: prev,
+            msg: format!("Expected {expected}, got unexpected EOF"),
+        }),
+    }
+}
+
+///
+/// Parses the components of a pattern written as a string literal, such as `"65816-*-snes-elf"`.
+///
+/// Each component is written as it would be outside of a string, but need not be an identifier. Components cannot be bound
+fn parse_str_pattern(pat: &str, span: Span) -> Result<Vec<Component>, Error> {
+    let parts = pat.split('-').collect::<Vec<_>>();
+
+    if parts.len() > 4 {
+        return Err(Error {
+            span,
+            msg: format!("Expected at most 4 components, got `{pat}`"),
+        });
+    }
+
+    let mut left = Vec::new();
+
+    for (i, part) in parts.iter().enumerate() {
+        let has_tail = i + 1 < parts.len();
+
+        let (name, raw) = match part.strip_prefix("r#") {
+            Some(name) => (name, true),
+            None => (*part, false),
+        };
+
+        let frag = if name == "*" {
+            if has_tail {
+                Frag::WildcardPos
+            } else {
+                Frag::WildcardRest
+            }
+        } else if let Some(family) = name.strip_prefix('{') {
+            let Some(family) = family.strip_suffix('}') else {
+                return Err(Error {
+                    span,
+                    msg: format!("Expected `}}` at the end of `{name}`"),
+                });
+            };
+
+            let (family, or_later) = match family.strip_suffix('+') {
+                Some(family) => (family, true),
+                None => (family, false),
+            };
+
+            if family.is_empty() {
+                return Err(Error {
+                    span,
+                    msg: format!("Expected the name of a family, got `{name}`"),
+                });
+            }
+
+            Frag::Family {
+                name: family.to_string(),
+                or_later,
+                span,
+            }
+        } else if name.is_empty() {
+            return Err(Error {
+                span,
+                msg: format!("Expected a component, got an empty component in `{pat}`"),
+            });
+        } else {
+            Frag::Ident(name.to_string(), span)
+        };
+
+        left.push(Component {
+            frag,
+            binding: None,
+            raw,
+        });
+    }
+
+    Ok(left)
+}
+
+fn parse_pattern(
+    iter: &mut Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<(Vec<Component>, PatternEnd), Error> {
+    if let Some(TokenTree::Literal(_)) = iter.peek() {
+        let (pat, span) = parse_str_literal(iter.next().unwrap())?;
+        let left = parse_str_pattern(&pat, span)?;
+        let end = parse_pattern_end(iter, span, "`|`, `if`, or `=>` after a string pattern")?;
+
+        return Ok((left, end));
+    }
 
     let mut left = Vec::new();
-    let mut right = Vec::new();
 
     loop {
         let Some(frag) = iter.next() else {
             return Err(Error {
                 span: Span::call_site(),
-                msg: format!("Unexpected EOF"),
+                msg: "Unexpected EOF".to_string(),
             });
         };
 
-        match iter.next() {
-            Some(TokenTree::Punct(p)) => {
-                if p.as_char() == '-' {
-                    if left.len() == 4 {
-                        return Err(Error {
-                            span: p.span(),
-                            msg: format!("Expected at most 4 components"),
-                        });
-                    }
-                    left.push(try_into_frag(frag, true)?);
-                } else if p.as_char() == '=' && p.spacing() == Spacing::Joint {
+        let (frag, binding) = match frag {
+            TokenTree::Ident(id) if matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '@') =>
+            {
+                iter.next();
+                let Some(frag) = iter.next() else {
+                    return Err(Error {
+                        span: id.span(),
+                        msg: format!("Expected a component after `{id} @`, got unexpected EOF"),
+                    });
+                };
+                (frag, Some(id))
+            }
+            frag => (frag, None),
+        };
+
+        let raw = matches!(&frag, TokenTree::Ident(id) if id.to_string().starts_with("r#"));
+
+        if let Some(TokenTree::Punct(p)) = iter.peek() {
+            if p.as_char() == '-' {
+                if left.len() == 4 {
+                    return Err(Error {
+                        span: p.span(),
+                        msg: "Expected at most 4 components".to_string(),
+                    });
+                }
+                iter.next();
+                left.push(Component {
+                    frag: try_into_frag(frag, true)?,
+                    binding,
+                    raw,
+                });
+                continue;
+            }
+        }
+
+        let end = parse_pattern_end(iter, frag.span(), "`-`, `|`, `if`, or `=>`")?;
+
+        left.push(Component {
+            frag: try_into_frag(frag, false)?,
+            binding,
+            raw,
+        });
+        return Ok((left, end));
+    }
+}
+
+/// Prefixes `pat` with the binding of `comp`, if any
+fn bind(comp: &Component, pat: TokenStream) -> TokenStream {
+    match &comp.binding {
+        Some(name) => {
+            let mut ts = TokenStream::from_iter([
+                TokenTree::Ident(name.clone()),
+                TokenTree::Punct(Punct::new('@', Spacing::Alone)),
+            ]);
+            ts.extend(pat);
+            ts
+        }
+        None => pat,
+    }
+}
+
+fn no_binding(comp: &Component, what: &str) -> Result<(), Error> {
+    match &comp.binding {
+        Some(name) => Err(Error {
+            span: name.span(),
+            msg: format!("Cannot bind {what}"),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn arch_tokens(comp: &Component, dcrate: &TokenStream) -> Result<TokenStream, Error> {
+    let pat = match &comp.frag {
+        Frag::Ident(i, span) => {
+            let Ok(piece) = Architecture::from_str(i) else {
+                return Err(Error {
+                    span: *span,
+                    msg: format!("Unknown architecture {i}"),
+                });
+            };
+
+            piece.to_ctor(*span, dcrate)
+        }
+        Frag::WildcardPos => Wildcard.to_ctor(Span::call_site(), dcrate),
+        Frag::Family {
+            name,
+            or_later: true,
+            span,
+        } => {
+            let Ok(piece) = Architecture::from_str(name) else {
+                return Err(Error {
+                    span: *span,
+                    msg: format!("Unknown architecture {name}"),
+                });
+            };
+
+            if piece.family() != "x86" {
+                return Err(Error {
+                    span: *span,
+                    msg: format!(
+                        "Only x86 architectures have microarchitecture levels, got {name}"
+                    ),
+                });
+            }
+
+            OrLater(piece).to_ctor(*span, dcrate)
+        }
+        Frag::Family {
+            name,
+            or_later: false,
+            span,
+        } => {
+            let members = ARCHITECTURES
+                .iter()
+                .filter(|arch| arch.family() == name)
+                .map(|arch| AnyLevel(*arch).to_ctor(*span, dcrate))
+                .collect::<Vec<_>>();
+
+            if members.is_empty() {
+                return Err(Error {
+                    span: *span,
+                    msg: format!("Unknown architecture family {name}"),
+                });
+            }
+
+            alternatives(members)
+        }
+        Frag::WildcardRest => unreachable!(),
+    };
+
+    Ok(bind(comp, pat))
+}
+
+///
+/// Parses the vendor named `name`.
+///
+/// Since every unknown vendor parses as [`Vendor::Unknown`], a name the library does not know is rejected as a likely typo,
+///  unless it is spelled `unknown`, or written as a raw identifier (such as `r#mycorp`) to match a custom vendor
+fn parse_vendor(name: &str, raw: bool, span: Span) -> Result<Vendor, Error> {
+    let vendor = Vendor::parse(name);
+
+    if vendor == Vendor::Unknown && name != "unknown" && !raw {
+        return Err(Error {
+            span,
+            msg: format!(
+                "Unknown vendor {name}. Write `unknown` to match targets with an unknown vendor, or `r#{name}` to match a custom vendor"
+            ),
+        });
+    }
+
+    Ok(vendor)
+}
+
+fn vendor_tokens(comp: &Component, dcrate: &TokenStream) -> Result<TokenStream, Error> {
+    unsupported_family(comp, "vendors")?;
+
+    let pat = match &comp.frag {
+        Frag::Ident(i, span) => parse_vendor(i, comp.raw, *span)?.to_ctor(*span, dcrate),
+        Frag::WildcardPos => Wildcard.to_ctor(Span::call_site(), dcrate),
+        Frag::WildcardRest | Frag::Family { .. } => unreachable!(),
+    };
+
+    Ok(bind(comp, pat))
+}
+
+/// The patterns of the operating system, environment, and object format for a system of a single component, such as `elf`,
+///  or a family of operating systems, such as `{bsd}`. A binding of a family binds the [`OS`]
+fn sys_tokens(comp: &Component, dcrate: &TokenStream) -> Result<[TokenStream; 3], Error> {
+    if let Frag::Family { name, span, .. } = &comp.frag {
+        let mut some: TokenStream =
+            emit_path(dcrate, ["__core", "option", "Option", "Some"], *span).collect();
+        some.extend([TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            bind(comp, os_family(name, *span, dcrate)?),
+        ))]);
+
+        return Ok([
+            some,
+            Wildcard.to_ctor(Span::call_site(), dcrate),
+            Wildcard.to_ctor(Span::call_site(), dcrate),
+        ]);
+    }
+
+    no_binding(comp, "the system of a target")?;
+
+    let Frag::Ident(sys, span) = &comp.frag else {
+        unreachable!()
+    };
+
+    let Ok(piece) = System::from_str(sys) else {
+        return Err(Error {
+            span: *span,
+            msg: format!("Unknown system {sys}"),
+        });
+    };
+
+    Ok([
+        piece.os().to_ctor(*span, dcrate),
+        piece.env().to_ctor(*span, dcrate),
+        piece.object_format().to_ctor(*span, dcrate),
+    ])
+}
+
+/// The pattern of the operating system in a pattern of 4 components. A binding binds the [`OS`] itself
+fn os_tokens(comp: &Component, dcrate: &TokenStream) -> Result<TokenStream, Error> {
+    let (pat, span) = match &comp.frag {
+        Frag::Ident(os, span) => {
+            let Ok(piece) = OS::from_str(os) else {
+                return Err(Error {
+                    span: *span,
+                    msg: format!("Unknown operating system {os}"),
+                });
+            };
+
+            (piece.to_ctor(*span, dcrate), *span)
+        }
+        Frag::Family { name, span, .. } => (os_family(name, *span, dcrate)?, *span),
+        Frag::WildcardPos => (
+            Wildcard.to_ctor(Span::call_site(), dcrate),
+            Span::call_site(),
+        ),
+        Frag::WildcardRest => unreachable!(),
+    };
+
+    let mut some: TokenStream =
+        emit_path(dcrate, ["__core", "option", "Option", "Some"], span).collect();
+    some.extend([TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        bind(comp, pat),
+    ))]);
+
+    Ok(some)
+}
+
+/// The patterns of the environment and object format in a pattern of 4 components.
+/// A binding binds the environment, as an [`Option<Environment>`]
+fn envobj_tokens(comp: &Component, dcrate: &TokenStream) -> Result<[TokenStream; 2], Error> {
+    unsupported_family(comp, "environments")?;
+
+    match &comp.frag {
+        Frag::Ident(i, span) => {
+            let Ok(piece) = System::from_str(i) else {
+                return Err(Error {
+                    span: *span,
+                    msg: format!("Unknown system {i}"),
+                });
+            };
+
+            let None = piece.os() else {
+                return Err(Error {
+                    span: *span,
+                    msg: format!("Operating system {i} not expected"),
+                });
+            };
+
+            Ok([
+                bind(comp, piece.env().to_ctor(*span, dcrate)),
+                piece.object_format().to_ctor(*span, dcrate),
+            ])
+        }
+        _ => Ok([
+            bind(comp, Wildcard.to_ctor(Span::call_site(), dcrate)),
+            Wildcard.to_ctor(Span::call_site(), dcrate),
+        ]),
+    }
+}
+
+fn pattern_tokens(left: &[Component], dcrate: &TokenStream) -> Result<TokenStream, Error> {
+    let mut elems = Vec::new();
+
+    match left {
+        [rest @ Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {
+            no_binding(rest, "the whole target")?;
+            return Ok(Wildcard.to_ctor(Span::call_site(), dcrate));
+        }
+        [arch, rest @ Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {
+            elems.push(arch_tokens(arch, dcrate)?);
+            // A wildcard in the position of the vendor may bind the vendor
+            elems.push(bind(rest, Wildcard.to_ctor(Span::call_site(), dcrate)));
+        }
+        [arch, vendor, rest @ Component {
+            frag: Frag::WildcardRest,
+            ..
+        }] => {
+            no_binding(rest, "the system of a target")?;
+            elems.push(arch_tokens(arch, dcrate)?);
+            elems.push(vendor_tokens(vendor, dcrate)?);
+        }
+        [arch, vendor, sys @ Component {
+            frag: Frag::Ident(..) | Frag::Family { .. },
+            ..
+        }] => {
+            elems.push(arch_tokens(arch, dcrate)?);
+            elems.push(vendor_tokens(vendor, dcrate)?);
+            elems.extend(sys_tokens(sys, dcrate)?);
+        }
+        [arch, vendor, os, envobj] => {
+            elems.push(arch_tokens(arch, dcrate)?);
+            elems.push(vendor_tokens(vendor, dcrate)?);
+            elems.push(os_tokens(os, dcrate)?);
+            elems.extend(envobj_tokens(envobj, dcrate)?);
+        }
+        [.., Component {
+            frag: Frag::Ident(_, span) | Frag::Family { span, .. },
+            ..
+        }] => {
+            return Err(Error {
+                span: *span,
+                msg: "Target must have at least 3 components if it doesn't end with a wildcard"
+                    .to_string(),
+            })
+        }
+        _ => unreachable!(),
+    }
+
+    while elems.len() < 5 {
+        elems.push(Wildcard.to_ctor(Span::call_site(), dcrate));
+    }
+
+    let mut tuple = TokenStream::new();
+    for (i, elem) in elems.into_iter().enumerate() {
+        if i != 0 {
+            tuple.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+        }
+        tuple.extend(elem);
+    }
+
+    Ok(TokenStream::from_iter([TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        tuple,
+    ))]))
+}
+
+/// An arm of `match_targets!`, along with the targets matched by each of its patterns
+struct Arm {
+    tokens: TokenStream,
+    /// The targets matched by each pattern (or `None` if unknown), and the span of the start of the pattern
+    patterns: Vec<(Option<Coverage>, Span)>,
+    guarded: bool,
+}
+
+fn parse_match_arm(
+    iter: &mut Peekable<impl Iterator<Item = TokenTree>>,
+    dcrate: &TokenStream,
+) -> Result<Option<Arm>, Error> {
+    let Some(_) = iter.peek() else {
+        return Ok(None);
+    };
+
+    let mut left_match = TokenStream::new();
+    let mut patterns = Vec::new();
+    let mut guarded = false;
+
+    loop {
+        let span = iter.peek().map_or(Span::call_site(), TokenTree::span);
+        let (left, end) = parse_pattern(iter)?;
+
+        if !left_match.is_empty() {
+            left_match.extend([TokenTree::Punct(Punct::new('|', Spacing::Alone))]);
+        }
+        left_match.extend(pattern_tokens(&left, dcrate)?);
+        patterns.push((coverage(&left), span));
+
+        match end {
+            PatternEnd::Or => continue,
+            PatternEnd::Guard(kw) => {
+                guarded = true;
+                left_match.extend([TokenTree::Ident(kw)]);
+                loop {
                     match iter.next() {
-                        Some(TokenTree::Punct(p)) if p.as_char() == '>' => {
-                            left.push(try_into_frag(frag, false)?);
+                        Some(TokenTree::Punct(p))
+                            if p.as_char() == '='
+                                && p.spacing() == Spacing::Joint
+                                && matches!(iter.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '>') =>
+                        {
+                            iter.next();
                             break;
                         }
-                        Some(p) => {
-                            return Err(Error {
-                                span: p.span(),
-                                msg: format!("Expected `=>`, got `{p}`"),
-                            })
-                        }
+                        Some(tt) => left_match.extend([tt]),
                         None => {
                             return Err(Error {
-                                span: p.span(),
-                                msg: format!("Expected `=>`, got unexpected EOF"),
+                                span: Span::call_site(),
+                                msg: "Expected `=>` after guard, got unexpected EOF".to_string(),
                             })
                         }
                     }
-                } else {
-                    return Err(Error {
-                        span: p.span(),
-                        msg: format!("Expected `-`, got `{p}`"),
-                    });
                 }
+                break;
             }
-            Some(tt) => {
-                return Err(Error {
-                    span: tt.span(),
-                    msg: format!("Expected `-`, got `{tt}`"),
-                })
-            }
-            None => {
-                return Err(Error {
-                    span: frag.span(),
-                    msg: format!("Expected `-` or `=>`, got unexpected EOF"),
-                })
-            }
+            PatternEnd::Arrow => break,
         }
     }
 
+    let right = parse_arm_body(iter)?;
+
+    let mut tt = left_match;
+    tt.extend([
+        TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+        TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+    ]);
+    tt.extend(right);
+
+    tt.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+
+    Ok(Some(Arm {
+        tokens: tt,
+        patterns,
+        guarded,
+    }))
+}
+
+/// Parses the body of an arm, after the `=>`, along with the `,` that ends it (if any)
+fn parse_arm_body(
+    iter: &mut Peekable<impl Iterator<Item = TokenTree>>,
+) -> Result<Vec<TokenTree>, Error> {
+    let mut right = Vec::new();
+
     loop {
         let Some(expr_comp) = iter.next() else {
             return Err(Error {
                 span: Span::call_site(),
-                msg: format!("Unexpected EOF"),
+                msg: "Unexpected EOF".to_string(),
             });
         };
 
@@ -290,246 +1106,398 @@ fn parse_match_arm(
         }
     }
 
-    let mut left_match = Vec::new();
+    Ok(right)
+}
 
-    match &*left {
-        [Frag::WildcardRest] => {
-            left_match.push(TokenTree::Ident(Ident::new("_", Span::call_site())))
+///
+/// Parses the arm `!unparsed(err) => body` after the `!`, which handles a target that fails to parse.
+///
+/// Returns the pattern of the error, and the body
+fn parse_unparsed_arm(
+    iter: &mut Peekable<impl Iterator<Item = TokenTree>>,
+    bang: Span,
+) -> Result<(TokenStream, TokenStream), Error> {
+    match iter.next() {
+        Some(TokenTree::Ident(id)) if id.to_string() == "unparsed" => {}
+        Some(tt) => {
+            return Err(Error {
+                span: tt.span(),
+                msg: format!("Expected `unparsed` after `!`, got `{tt}`"),
+            })
+        }
+        None => {
+            return Err(Error {
+                span: bang,
+                msg: "Expected `unparsed` after `!`, got unexpected EOF".to_string(),
+            })
         }
-        [arch, Frag::WildcardRest] => {
-            let mut arch = match arch {
-                Frag::Ident(i, span) => {
-                    let Ok(piece) = Architecture::from_str(i) else {
-                        return Err(Error {
-                            span: *span,
-                            msg: format!("Unknown architecture {i}"),
-                        });
-                    };
+    }
 
-                    let targ = piece.into_ctor(*span, dcrate);
-                    targ
-                }
-                Frag::WildcardPos => Wildcard.into_ctor(Span::call_site(), dcrate),
-                _ => unreachable!(),
-            };
+    let pat = match iter.next() {
+        Some(TokenTree::Group(g)) if g.delimiter() == Delimiter::Parenthesis => g.stream(),
+        Some(tt) => {
+            return Err(Error {
+                span: tt.span(),
+                msg: format!("Expected the pattern of the error in `()`, got `{tt}`"),
+            })
+        }
+        None => {
+            return Err(Error {
+                span: bang,
+                msg: "Expected the pattern of the error in `()`, got unexpected EOF".to_string(),
+            })
+        }
+    };
+
+    match parse_pattern_end(iter, bang, "`=>`")? {
+        PatternEnd::Arrow => {}
+        PatternEnd::Or | PatternEnd::Guard(_) => {
+            return Err(Error {
+                span: bang,
+                msg: "The `!unparsed` arm cannot have alternatives or a guard".to_string(),
+            })
+        }
+    }
 
-            pad_with_wildcard(&mut arch, 1);
+    Ok((pat, parse_arm_body(iter)?.into_iter().collect()))
+}
 
-            left_match.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, arch)));
+///
+/// Applies `#[cfg(...)]` to `item`, with the predicate on the `target_*` cfgs that is equivalent to the target patterns in `attr`.
+///
+/// See the documentation of the re-export in `target-tuples`
+#[proc_macro_attribute]
+pub fn cfg_target(attr: TokenStream, item: TokenStream) -> TokenStream {
+    // `cfg(target_abi)` is stable since Rust 1.78, so an earlier compiler cannot check a pattern that names a system
+    let pred = impl_cfg_target(attr).and_then(|pred| {
+        if cfg!(not(has_target_abi)) && pred.checks("target_abi") {
+            Err(Error {
+                span: Span::call_site(),
+                msg: "A pattern that names a system checks `cfg(target_abi)`, which requires Rust 1.78 or later".to_string(),
+            })
+        } else {
+            Ok(pred)
+        }
+    });
+
+    let mut ts = match pred {
+        Ok(pred) => TokenStream::from_iter([
+            TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+            TokenTree::Group(Group::new(
+                Delimiter::Bracket,
+                TokenStream::from_iter([
+                    TokenTree::Ident(Ident::new("cfg", Span::call_site())),
+                    TokenTree::Group(Group::new(
+                        Delimiter::Parenthesis,
+                        pred.into_tokens(Span::call_site()),
+                    )),
+                ]),
+            )),
+        ]),
+        Err(e) => {
+            let mut ts = emit_error(e);
+            ts.extend([TokenTree::Punct(Punct::new(';', Spacing::Alone))]);
+            ts
         }
-        [arch, vendor, Frag::WildcardRest] => {
-            let mut arch = match arch {
-                Frag::Ident(i, span) => {
-                    let Ok(piece) = Architecture::from_str(i) else {
-                        return Err(Error {
-                            span: *span,
-                            msg: format!("Unknown architecture {i}"),
-                        });
-                    };
+    };
 
-                    let targ = piece.into_ctor(*span, dcrate);
-                    targ
-                }
-                Frag::WildcardPos => Wildcard.into_ctor(Span::call_site(), dcrate),
-                _ => unreachable!(),
-            };
+    ts.extend(item);
+    ts
+}
 
-            let vendor = match vendor {
-                Frag::Ident(i, span) => {
-                    let Ok(piece) = Vendor::from_str(i) else {
-                        unreachable!()
-                    };
+///
+/// Expands to the predicate that `#[cfg_target]` applies for the target patterns, as a string literal
+#[doc(hidden)]
+#[proc_macro]
+pub fn __cfg_target_predicate(ts: TokenStream) -> TokenStream {
+    match impl_cfg_target(ts) {
+        Ok(pred) => {
+            let pred = pred.into_tokens(Span::call_site());
+            TokenTree::Literal(Literal::string(&pred.to_string())).into()
+        }
+        Err(e) => emit_error(e),
+    }
+}
 
-                    let targ = piece.into_ctor(*span, dcrate);
-                    targ
-                }
-                Frag::WildcardPos => Wildcard.into_ctor(Span::call_site(), dcrate),
-                _ => unreachable!(),
-            };
-            arch.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-            arch.extend(vendor);
-            pad_with_wildcard(&mut arch, 2);
-
-            left_match.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, arch)));
-        }
-        [arch, vendor, Frag::Ident(sys, span)] => {
-            let mut arch = match arch {
-                Frag::Ident(i, span) => {
-                    let Ok(piece) = Architecture::from_str(i) else {
-                        return Err(Error {
-                            span: *span,
-                            msg: format!("Unknown architecture {i}"),
-                        });
-                    };
-
-                    let targ = piece.into_ctor(*span, dcrate);
-                    targ
-                }
-                Frag::WildcardPos => Wildcard.into_ctor(Span::call_site(), dcrate),
-                _ => unreachable!(),
-            };
+fn impl_cfg_target(attr: TokenStream) -> Result<Pred, Error> {
+    if attr.is_empty() {
+        return Err(Error {
+            span: Span::call_site(),
+            msg: "Expected a target pattern".to_string(),
+        });
+    }
 
-            let vendor = match vendor {
-                Frag::Ident(i, span) => {
-                    let Ok(piece) = Vendor::from_str(i) else {
-                        unreachable!()
-                    };
+    // The patterns are parsed as the patterns of a match arm without a body.
+    // A pattern passed through `macro_rules!` as a `literal` fragment is unwrapped from its invisible group
+    let mut iter = attr
+        .into_iter()
+        .map(strip_none_group)
+        .chain([
+            TokenTree::Punct(Punct::new('=', Spacing::Joint)),
+            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+        ])
+        .peekable();
 
-                    let targ = piece.into_ctor(*span, dcrate);
-                    targ
-                }
-                Frag::WildcardPos => Wildcard.into_ctor(Span::call_site(), dcrate),
-                _ => unreachable!(),
-            };
-            let sys = {
-                let Ok(piece) = System::from_str(sys) else {
-                    return Err(Error {
-                        span: *span,
-                        msg: format!("Unknown system {sys}"),
-                    });
-                };
+    let mut preds = Vec::new();
 
-                let os = piece.os();
-                let env = piece.env();
-                let objfmt = piece.object_format();
+    loop {
+        let (left, end) = parse_pattern(&mut iter)?;
 
-                let mut ctor = os.into_ctor(*span, dcrate);
+        for comp in &left {
+            no_binding(comp, "components in #[cfg_target]")?;
+        }
+        pattern_tokens(&left, &TokenStream::new())?;
+        preds.push(cfg_predicate(&left).expect("the pattern has been validated"));
 
-                ctor.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-                ctor.extend(env.into_ctor(*span, dcrate));
-                ctor.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-                ctor.extend(objfmt.into_ctor(*span, dcrate));
+        match end {
+            PatternEnd::Or => continue,
+            PatternEnd::Guard(kw) => {
+                return Err(Error {
+                    span: kw.span(),
+                    msg: "Guards are not supported in #[cfg_target]".to_string(),
+                })
+            }
+            PatternEnd::Arrow => break,
+        }
+    }
 
-                ctor
-            };
+    if let Some(tt) = iter.next() {
+        return Err(Error {
+            span: tt.span(),
+            msg: format!("Unexpected `{tt}` after the target patterns"),
+        });
+    }
 
-            arch.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-            arch.extend(vendor);
-            arch.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-            arch.extend(sys);
+    Ok(Pred::any(preds))
+}
 
-            left_match.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, arch)));
-        }
-        [arch, vendor, os, envobj] => {
-            let mut arch = match arch {
-                Frag::Ident(i, span) => {
-                    let Ok(piece) = Architecture::from_str(i) else {
-                        return Err(Error {
-                            span: *span,
-                            msg: format!("Unknown architecture {i}"),
-                        });
-                    };
-
-                    let targ = piece.into_ctor(*span, dcrate);
-                    targ
-                }
-                Frag::WildcardPos => Wildcard.into_ctor(Span::call_site(), dcrate),
-                _ => unreachable!(),
-            };
+#[proc_macro]
+pub fn __target(ts: TokenStream) -> TokenStream {
+    let mut stream = ts.into_iter();
 
-            let vendor = match vendor {
-                Frag::Ident(i, span) => {
-                    let Ok(piece) = Vendor::from_str(i) else {
-                        unreachable!()
-                    };
+    let dollar_crate = match stream.next().unwrap() {
+        TokenTree::Group(g) => g.stream(),
+        _ => panic!("Invalid syntax"),
+    };
 
-                    let targ = piece.into_ctor(*span, dcrate);
-                    targ
-                }
-                Frag::WildcardPos => Wildcard.into_ctor(Span::call_site(), dcrate),
-                _ => unreachable!(),
-            };
-            let os = match os {
-                Frag::Ident(os, span) => {
-                    let Ok(piece) = OS::from_str(os) else {
-                        return Err(Error {
-                            span: *span,
-                            msg: format!("Unknown operating system {os}"),
-                        });
-                    };
-
-                    Some(piece).into_ctor(*span, dcrate)
-                }
-                Frag::WildcardPos => Some(Wildcard).into_ctor(Span::call_site(), dcrate),
-                _ => unreachable!(),
-            };
-            let sys = {
-                match envobj {
-                    Frag::Ident(i, span) => {
-                        let Ok(piece) = System::from_str(i) else {
-                            return Err(Error {
-                                span: *span,
-                                msg: format!("Unknown system {i}"),
-                            });
-                        };
+    match impl_target(&dollar_crate, stream) {
+        Ok(ts) => ts,
+        Err(e) => emit_error(e),
+    }
+}
 
-                        let None = piece.os() else {
-                            return Err(Error {
-                                span: *span,
-                                msg: format!("Operating system {i} not expected"),
-                            });
-                        };
-                        let env = piece.env();
-                        let objfmt = piece.object_format();
+/// Unwraps the invisible groups introduced by passing a fragment through `macro_rules!`
+fn strip_none_group(tt: TokenTree) -> TokenTree {
+    match tt {
+        TokenTree::Group(g) if g.delimiter() == Delimiter::None => {
+            let mut inner = g.stream().into_iter();
+            match (inner.next(), inner.next()) {
+                (Some(tt), None) => strip_none_group(tt),
+                _ => TokenTree::Group(g),
+            }
+        }
+        tt => tt,
+    }
+}
+
+fn parse_str_literal(tt: TokenTree) -> Result<(String, Span), Error> {
+    let tt = strip_none_group(tt);
+    let span = tt.span();
+    let lit = match tt {
+        TokenTree::Literal(lit) => lit.to_string(),
+        tt => {
+            return Err(Error {
+                span,
+                msg: format!("Expected a string literal, got `{tt}`"),
+            })
+        }
+    };
 
-                        let mut ctor = env.into_ctor(*span, dcrate);
+    let value = if let Some(raw) = lit.strip_prefix('r') {
+        let hashes = raw.len() - raw.trim_start_matches('#').len();
+        raw.get((hashes + 1)..(raw.len() - hashes - 1))
+    } else if lit.contains('\\') {
+        return Err(Error {
+            span,
+            msg: "Escape sequences are not permitted in target names".to_string(),
+        });
+    } else {
+        lit.strip_prefix('"').and_then(|lit| lit.strip_suffix('"'))
+    };
 
-                        ctor.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-                        ctor.extend(objfmt.into_ctor(*span, dcrate));
+    match value {
+        Some(value) => Ok((value.to_string(), span)),
+        None => Err(Error {
+            span,
+            msg: format!("Expected a string literal, got `{lit}`"),
+        }),
+    }
+}
 
-                        ctor
-                    }
-                    _ => {
-                        let mut some = Some(Wildcard).into_ctor(Span::call_site(), dcrate);
-                        some.extend([TokenTree::Punct(Punct::new('|', Spacing::Alone))]);
-                        some.extend(None::<Wildcard>.into_ctor(Span::call_site(), dcrate));
+/// Underlines `range` of the target `name`, on the line after it.
+/// A span cannot point within a string literal on stable, so errors in a component of a target point it out with this instead
+fn underline(name: &str, range: std::ops::Range<usize>) -> String {
+    format!(
+        "{name}\n{}{}",
+        " ".repeat(range.start),
+        "^".repeat(range.len())
+    )
+}
 
-                        let mut rest = some.clone();
-                        rest.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-                        rest.extend(some);
+fn impl_target(
+    dollar_crate: &TokenStream,
+    mut iter: impl Iterator<Item = TokenTree>,
+) -> Result<TokenStream, Error> {
+    let (by_ref, name) = match iter.next() {
+        Some(TokenTree::Ident(id)) if id.to_string() == "ref" => (true, iter.next()),
+        tt => (false, tt),
+    };
 
-                        rest
-                    }
-                }
-            };
+    let Some(name) = name else {
+        return Err(Error {
+            span: Span::call_site(),
+            msg: "Unexpected EOF".to_string(),
+        });
+    };
 
-            arch.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-            arch.extend(vendor);
-            arch.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-            arch.extend(os);
-            arch.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
-            arch.extend(sys);
+    let (name, span) = parse_str_literal(name)?;
 
-            left_match.push(TokenTree::Group(Group::new(Delimiter::Parenthesis, arch)));
-        }
-        [.., Frag::Ident(_, span)] => {
+    if let Some(tt) = iter.next() {
+        return Err(Error {
+            span: tt.span(),
+            msg: format!("Unexpected token `{tt}`"),
+        });
+    }
+
+    let (arch, vendor, sys) = match parse_target(&name) {
+        Ok(pieces) => pieces,
+        Err(err) if err.range.is_empty() => {
             return Err(Error {
-                span: *span,
+                span,
                 msg: format!(
-                    "Target must have at least 3 components if it doesn't end with a wildcard"
+                    "Expected a target of the form `<arch>-<vendor>-<system>`, got `{name}`"
                 ),
             })
         }
-        _ => unreachable!(),
-    }
+        Err(InvalidComponent { component, range }) => {
+            let what = match component {
+                target_tuple_pieces::Component::Architecture => "architecture",
+                target_tuple_pieces::Component::Vendor => "vendor",
+                target_tuple_pieces::Component::System => "system",
+            };
 
-    let mut tt = left_match.into_iter().collect::<TokenStream>();
-    tt.extend([
-        TokenTree::Punct(Punct::new('=', Spacing::Joint)),
-        TokenTree::Punct(Punct::new('>', Spacing::Alone)),
-    ]);
-    tt.extend(right);
+            return Err(Error {
+                span,
+                msg: format!(
+                    "Unknown {what} {}\n{}",
+                    &name[range.clone()],
+                    underline(&name, range)
+                ),
+            });
+        }
+    };
 
-    tt.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+    // As in a pattern, an unknown vendor is rejected as a likely typo, unless it is written as a raw identifier
+    let (name, vendor) = match vendor {
+        Some(Vendor::Unknown) => {
+            let start = name.find('-').unwrap() + 1;
+            let end = start + name[start..].find('-').unwrap();
+            let written = &name[start..end];
+
+            if let Some(custom) = written.strip_prefix("r#") {
+                let name = format!("{}{custom}{}", &name[..start], &name[end..]);
+                let vendor = Some(Vendor::parse(custom));
+                (name, vendor)
+            } else if written == "unknown" {
+                (name, vendor)
+            } else {
+                return Err(Error {
+                    span,
+                    msg: format!(
+                        "Unknown vendor {written}. Write `unknown` for a target with an unknown vendor, or `r#{written}` for a custom vendor\n{}",
+                        underline(&name, start..end)
+                    ),
+                });
+            }
+        }
+        vendor => (name, vendor),
+    };
 
-    Ok(Some(tt))
-}
+    let comma = || TokenTree::Punct(Punct::new(',', Spacing::Alone));
 
-fn pad_with_wildcard(targ: &mut TokenStream, components: usize) {
-    for _ in components..5 {
-        targ.extend([
-            TokenTree::Punct(Punct::new(',', Spacing::Alone)),
-            TokenTree::Ident(Ident::new("_", Span::call_site())),
-        ])
-    }
+    let mut sys_args = TokenStream::new();
+    let sys_ctor = match sys.kernel() {
+        Some(kernel) if Some(kernel) != Kernel::infer(sys.os(), sys.env()) => {
+            sys_args.extend(kernel.to_ctor(span, dollar_crate));
+            sys_args.extend([comma()]);
+            sys_args.extend(sys.env().to_ctor(span, dollar_crate));
+            "from_kernel"
+        }
+        _ => {
+            sys_args.extend(sys.os().to_ctor(span, dollar_crate));
+            sys_args.extend([comma()]);
+            sys_args.extend(sys.env().to_ctor(span, dollar_crate));
+            sys_args.extend([comma()]);
+            sys_args.extend(sys.object_format().to_ctor(span, dollar_crate));
+            "from_pieces"
+        }
+    };
+
+    let mut args = arch.to_ctor(span, dollar_crate);
+    args.extend([comma()]);
+    args.extend(vendor.to_ctor(span, dollar_crate));
+    args.extend([comma()]);
+    args.extend(emit_path(
+        dollar_crate,
+        ["pieces", "System", sys_ctor],
+        span,
+    ));
+    args.extend([TokenTree::Group(Group::new(
+        Delimiter::Parenthesis,
+        sys_args,
+    ))]);
+
+    let mut value: TokenStream =
+        emit_path(dollar_crate, ["CanonicalTarget", "new"], span).collect();
+    value.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, args))]);
+
+    let ty = if by_ref {
+        let mut args =
+            TokenStream::from_iter([TokenTree::Literal(Literal::string(&name)), comma()]);
+        args.extend(value);
+
+        value = emit_path(dollar_crate, ["TargetRef", "__from_parts"], span).collect();
+        value.extend([TokenTree::Group(Group::new(Delimiter::Parenthesis, args))]);
+
+        let mut ty: TokenStream = emit_path(dollar_crate, ["TargetRef"], span).collect();
+        ty.extend([
+            TokenTree::Punct(Punct::new('<', Spacing::Alone)),
+            TokenTree::Punct(Punct::new('\'', Spacing::Joint)),
+            TokenTree::Ident(Ident::new("static", span)),
+            TokenTree::Punct(Punct::new('>', Spacing::Alone)),
+        ]);
+        ty
+    } else {
+        emit_path(dollar_crate, ["CanonicalTarget"], span).collect()
+    };
+
+    // Evaluate the target in a `const` item, so that it is computed at compile time even when used at runtime
+    let var_span = span.resolved_at(Span::mixed_site());
+    let mut inner = TokenStream::from_iter([
+        TokenTree::Ident(Ident::new("const", var_span)),
+        TokenTree::Ident(Ident::new_raw("__TARGET", var_span)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+    ]);
+    inner.extend(ty);
+    inner.extend([TokenTree::Punct(Punct::new('=', Spacing::Alone))]);
+    inner.extend(value);
+    inner.extend([
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+        TokenTree::Ident(Ident::new_raw("__TARGET", var_span)),
+    ]);
+
+    Ok(TokenStream::from_iter([TokenTree::Group(Group::new(
+        Delimiter::Brace,
+        inner,
+    ))]))
 }
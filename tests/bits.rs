@@ -0,0 +1,100 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use target_tuples::{pieces::Kernel, target, CanonicalTarget};
+
+fn check_roundtrip(name: &str) {
+    // Some rustc targets are not yet known to the library
+    let Ok(targ) = CanonicalTarget::from_alias(name) else {
+        return;
+    };
+    let bits = targ.to_bits();
+
+    assert_eq!(
+        CanonicalTarget::from_bits(bits).ok(),
+        Some(targ),
+        "{name}: {bits:#018x}"
+    );
+}
+
+#[test]
+fn test_roundtrip() -> std::io::Result<()> {
+    for file in [
+        "/tests/rustc-targets.data",
+        "/tests/config-sub.data",
+        "/tests/alias.data",
+    ] {
+        let f = BufReader::new(File::open(format!(
+            "{}{}",
+            env!("CARGO_MANIFEST_DIR"),
+            file
+        ))?);
+        for s in f.lines() {
+            let s = s?;
+            if s.trim().is_empty() {
+                continue;
+            }
+            for name in s.split('|') {
+                check_roundtrip(name);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The encoding is stable, so these values must never change
+#[test]
+fn test_stable_encoding() {
+    const ENCODINGS: &[(CanonicalTarget, u64)] = &[
+        (target!("x86_64-pc-linux-gnu"), 0x0000_020a_0203_0103),
+        (target!("x86_64v3-linux-musl"), 0x0000_0c0a_0200_0303),
+        (target!("i386-lilium-kernel"), 0x0000_192b_0000_0302),
+        (target!("aarch64-apple-darwin"), 0x0000_0004_0002_0006),
+        (target!("m68k-uclinux-uclibc"), 0x0000_1a0a_0300_001d),
+        (target!("wc65c816-wdc-snes-elf"), 0x0004_0026_0010_0017),
+    ];
+
+    for (targ, bits) in ENCODINGS {
+        assert_eq!(targ.to_bits(), *bits, "{targ}: {:#018x}", targ.to_bits());
+        assert_eq!(CanonicalTarget::from_bits(*bits).ok(), Some(*targ));
+    }
+}
+
+#[test]
+fn test_invalid_bits() {
+    let linux = target!("x86_64-pc-linux-gnu").to_bits();
+
+    // No system
+    assert!(CanonicalTarget::from_bits(0x0000_0000_0003_0103).is_err());
+    // Reserved byte
+    assert!(CanonicalTarget::from_bits(linux | 1 << 56).is_err());
+    // Unknown architecture
+    assert!(CanonicalTarget::from_bits(linux | 0xff).is_err());
+    // Microarchitecture on a non-x86 architecture
+    assert!(
+        CanonicalTarget::from_bits(target!("aarch64-apple-darwin").to_bits() | 1 << 8).is_err()
+    );
+    // Kernel of another operating system
+    assert!(CanonicalTarget::from_bits(linux & !(0xff << 24) | 0x04 << 24).is_err());
+}
+
+#[test]
+fn test_kernel_not_inferred() {
+    let linux = target!("x86_64-pc-linux-gnu");
+    let uclinux = target!("m68k-uclinux-uclibc");
+
+    // An absent kernel is inferred
+    assert_eq!(
+        CanonicalTarget::from_bits(linux.to_bits() & !(0xff << 24)).ok(),
+        Some(linux)
+    );
+    // A kernel that differs from the inferred one is kept
+    assert_eq!(
+        CanonicalTarget::from_bits(uclinux.to_bits()).ok(),
+        Some(uclinux)
+    );
+    assert_ne!(
+        uclinux.sys.kernel(),
+        Kernel::infer(uclinux.sys.os(), uclinux.sys.env())
+    );
+}
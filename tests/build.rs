@@ -0,0 +1,109 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use target_tuples::build::{custom_cfgs, mismatches, CargoCfg, Mismatch};
+use target_tuples::pieces::Architecture;
+use target_tuples::TargetRef;
+
+fn read_cfgs() -> std::io::Result<Vec<(String, CargoCfg)>> {
+    let f = BufReader::new(File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/rustc-cfgs.data"
+    ))?);
+    let mut cfgs = Vec::new();
+    for s in f.lines() {
+        let s = s?;
+        let s = s.trim();
+        if s.is_empty() || s.starts_with('#') {
+            continue;
+        }
+        let fields = s.split('|').collect::<Vec<_>>();
+        let cfg = CargoCfg {
+            arch: fields[1].into(),
+            vendor: fields[2].into(),
+            os: fields[3].into(),
+            env: fields[4].into(),
+            abi: fields[5].into(),
+            endian: fields[6].into(),
+            pointer_width: fields[7].into(),
+            features: fields[8]
+                .split(',')
+                .filter(|f| !f.is_empty())
+                .map(String::from)
+                .collect(),
+        };
+        cfgs.push((fields[0].to_string(), cfg));
+    }
+    Ok(cfgs)
+}
+
+#[test]
+fn test_no_mismatches() -> std::io::Result<()> {
+    for (name, cfg) in read_cfgs()? {
+        if let Ok(targ) = TargetRef::try_parse(&name) {
+            assert_eq!(mismatches(&targ, &cfg), vec![], "{}", name);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_mismatches() -> std::io::Result<()> {
+    let cfgs = read_cfgs()?;
+    let (_, x86_64_linux) = cfgs
+        .iter()
+        .find(|(name, _)| name == "x86_64-unknown-linux-gnu")
+        .unwrap();
+
+    let targ = TargetRef::parse("i686-unknown-linux-gnu");
+    assert_eq!(
+        mismatches(&targ, x86_64_linux),
+        vec![Mismatch::Arch {
+            tuple: Architecture::X86_32(6),
+            cfg: Architecture::X86_64 { microarch: 1 },
+        }]
+    );
+
+    // A newer microarchitecture enabled by `-C target-cpu` is not a mismatch, but an older one is
+    let targ = TargetRef::parse("x86_64v3-unknown-linux-gnu");
+    assert_eq!(mismatches(&targ, x86_64_linux).len(), 1);
+    let mut native = x86_64_linux.clone();
+    native
+        .features
+        .extend(["avx2".to_string(), "avx512f".to_string()]);
+    assert_eq!(mismatches(&targ, &native), vec![]);
+
+    let targ = TargetRef::parse("x86_64-unknown-linux-musl");
+    assert!(matches!(
+        &*mismatches(&targ, x86_64_linux),
+        [Mismatch::System { .. }]
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_custom_cfgs() {
+    let targ = TargetRef::parse("x86_64v3-unknown-linux-gnu");
+    assert_eq!(
+        custom_cfgs(&targ),
+        vec![
+            ("tuple_family", "x86"),
+            ("tuple_objfmt", "elf"),
+            ("tuple_microarch", "3")
+        ]
+    );
+
+    let targ = TargetRef::parse("aarch64-apple-darwin");
+    assert_eq!(
+        custom_cfgs(&targ),
+        vec![("tuple_family", "aarch64"), ("tuple_objfmt", "macho")]
+    );
+
+    let targ = TargetRef::parse("wasm32-unknown-wasi");
+    assert_eq!(
+        custom_cfgs(&targ),
+        vec![("tuple_family", "wasm"), ("tuple_objfmt", "wasm")]
+    );
+}
@@ -0,0 +1,113 @@
+use core::ffi::c_char;
+
+use target_tuples::capi::*;
+use target_tuples::{target, CanonicalTarget};
+
+const HEADER_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/target-tuples-capi/include/target_tuples.h"
+);
+
+fn parse(name: &str) -> Option<TargetTuple> {
+    let mut out = TargetTuple::default();
+    let res = unsafe { target_tuple_parse(name.as_ptr().cast(), name.len(), &mut out) };
+
+    match res {
+        0 => Some(out),
+        -1 => None,
+        res => panic!("Unexpected result {res}"),
+    }
+}
+
+fn name_of(name: TargetTupleName) -> Option<&'static str> {
+    if name.name.is_null() {
+        return None;
+    }
+    let bytes = unsafe { core::slice::from_raw_parts(name.name.cast::<u8>(), name.len) };
+    Some(core::str::from_utf8(bytes).unwrap())
+}
+
+#[test]
+fn test_parse() {
+    let targ = parse("x86_64-pc-linux-gnu").unwrap();
+
+    assert_eq!(targ, TargetTuple::from(target!("x86_64-pc-linux-gnu")));
+    assert_eq!(
+        CanonicalTarget::try_from(targ).ok(),
+        Some(target!("x86_64-pc-linux-gnu"))
+    );
+
+    assert_eq!(parse("x86_64-pc-notanos"), None);
+    assert_eq!(parse("\u{ff}"), None);
+
+    let mut out = TargetTuple::default();
+    assert_eq!(
+        unsafe { target_tuple_parse(core::ptr::null(), 0, &mut out) },
+        -1
+    );
+}
+
+#[test]
+fn test_format() {
+    let targ = parse("x86_64-linux-musl").unwrap();
+
+    let len = unsafe { target_tuple_format(&targ, core::ptr::null_mut(), 0) };
+    assert_eq!(len, "x86_64-pc-linux-musl".len());
+
+    let mut buf = [0x7f as c_char; 32];
+    let len = unsafe { target_tuple_format(&targ, buf.as_mut_ptr(), buf.len()) };
+    let bytes = buf.map(|c| c as u8);
+    assert_eq!(&bytes[..=len], b"x86_64-pc-linux-musl\0");
+
+    let mut buf = [0x7f as c_char; 7];
+    let len = unsafe { target_tuple_format(&targ, buf.as_mut_ptr(), buf.len()) };
+    let bytes = buf.map(|c| c as u8);
+    assert_eq!(len, "x86_64-pc-linux-musl".len());
+    assert_eq!(&bytes, b"x86_64\0");
+
+    let bad = TargetTuple { arch: 0xff, ..targ };
+    assert_eq!(
+        unsafe { target_tuple_format(&bad, buf.as_mut_ptr(), buf.len()) },
+        usize::MAX
+    );
+}
+
+#[test]
+fn test_names() {
+    let targ = parse("aarch64-linux-gnu").unwrap();
+
+    unsafe {
+        assert_eq!(name_of(target_tuple_arch_name(&targ)), Some("aarch64"));
+        assert_eq!(name_of(target_tuple_vendor_name(&targ)), Some("unknown"));
+        assert_eq!(name_of(target_tuple_os_name(&targ)), Some("linux"));
+        assert_eq!(name_of(target_tuple_env_name(&targ)), Some("gnu"));
+        assert_eq!(name_of(target_tuple_objfmt_name(&targ)), Some("elf"));
+    }
+
+    let targ = parse("i686-pc-elf").unwrap();
+
+    unsafe {
+        assert_eq!(name_of(target_tuple_arch_name(&targ)), Some("i686"));
+        assert_eq!(name_of(target_tuple_os_name(&targ)), None);
+        assert_eq!(name_of(target_tuple_env_name(&targ)), None);
+        assert_eq!(name_of(target_tuple_arch_name(core::ptr::null())), None);
+    }
+}
+
+/// The header shipped with target-tuples-capi must match the generated one.
+/// Run with `TARGET_TUPLES_BLESS=1` to regenerate it
+#[test]
+fn test_header() -> std::io::Result<()> {
+    let header = header();
+
+    if std::env::var_os("TARGET_TUPLES_BLESS").is_some() {
+        std::fs::write(HEADER_PATH, &header)?;
+    }
+
+    assert_eq!(std::fs::read_to_string(HEADER_PATH)?, header);
+    assert!(header.contains("#define TARGET_TUPLE_ARCH_X86_64 3\n"));
+    assert!(header.contains("#define TARGET_TUPLE_VENDOR_WDC 16\n"));
+    assert!(header.contains("#define TARGET_TUPLE_OS_LILIUM 43\n"));
+
+    Ok(())
+}
@@ -0,0 +1,298 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use target_tuples::cfg::CfgExpr;
+use target_tuples::pattern::TargetPattern;
+use target_tuples::pieces::{Architecture, System, Vendor, OS};
+use target_tuples::{CanonicalTarget, TargetRef};
+
+// `has_target_abi` is set by the build script, since `#[cfg_target]` needs Rust 1.78 for the patterns with a system
+#[cfg(has_target_abi)]
+mod fallback {
+    pub fn matches() -> bool {
+        false
+    }
+}
+
+/// Checks whether the item under `#[cfg_target(...)]` is compiled
+#[cfg(has_target_abi)]
+macro_rules! cfg_target_matches {
+    ($($pat:tt)*) => {{
+        mod m {
+            #[allow(unused_imports)]
+            pub use crate::fallback::*;
+
+            #[target_tuples::cfg_target($($pat)*)]
+            pub fn matches() -> bool {
+                true
+            }
+        }
+
+        m::matches()
+    }};
+}
+
+#[cfg(has_target_abi)]
+fn pattern_matches(pat: &str) -> bool {
+    pat.parse::<TargetPattern>()
+        .unwrap()
+        .matches(&CanonicalTarget::CURRENT)
+}
+
+/// Checks that `#[cfg_target]` agrees with [`TargetPattern`] on the current target
+#[cfg(has_target_abi)]
+macro_rules! check_patterns {
+    ($($pat:literal),* $(,)?) => {
+        $(
+            assert_eq!(
+                cfg_target_matches!($pat),
+                pattern_matches($pat),
+                "{} on {}",
+                $pat,
+                CanonicalTarget::CURRENT
+            );
+        )*
+    };
+}
+
+#[test]
+#[cfg(has_target_abi)]
+fn test_same_as_pattern() {
+    // The exact levels of x86 architectures are omitted, since cfgs only check the features of a level
+    check_patterns![
+        "*",
+        "{x86}-*",
+        "{x86_64+}-*",
+        "{i686+}-*",
+        "{arm}-*",
+        "aarch64-*",
+        "riscv64-*",
+        "wasm32-*",
+        "*-unknown-*",
+        "*-pc-*",
+        "*-apple-*",
+        "*-*-linux",
+        "*-*-linux-gnu",
+        "*-*-linux-musl",
+        "*-*-linux-*",
+        "*-*-android",
+        "*-*-*-gnu",
+        "*-*-*-msvc",
+        "*-*-{bsd}",
+        "*-*-{apple}",
+        "*-*-windows-msvc",
+        "*-*-windows-gnu",
+        "*-*-darwin",
+        "*-*-freebsd",
+        "aarch64-apple-darwin",
+        "aarch64-unknown-linux-gnu",
+        "{x86_64+}-unknown-linux-gnu",
+        "{x86_64+}-pc-windows-msvc",
+        "arm-unknown-linux-gnueabihf",
+    ];
+}
+
+#[test]
+#[cfg(has_target_abi)]
+fn test_syntax() {
+    assert_eq!(
+        cfg_target_matches!(x86_64 - unknown - linux - gnu),
+        cfg_target_matches!("x86_64-unknown-linux-gnu")
+    );
+    assert_eq!(
+        cfg_target_matches!({ x86 } - *-{ bsd }),
+        cfg_target_matches!("{x86}-*-{bsd}")
+    );
+    assert_eq!(
+        cfg_target_matches!(r#"*-*-linux-*"#),
+        cfg_target_matches!("*-*-linux-*")
+    );
+    assert_eq!(
+        cfg_target_matches!(aarch64-* | "*-*-linux-gnu"),
+        pattern_matches("aarch64-*") || pattern_matches("*-*-linux-gnu")
+    );
+    assert!(cfg_target_matches!(* | x86_64-*));
+}
+
+#[test]
+#[cfg(has_target_abi)]
+fn test_same_as_cfg() {
+    assert_eq!(
+        cfg_target_matches!("x86_64-*-linux-gnu"),
+        cfg!(all(
+            target_arch = "x86_64",
+            target_os = "linux",
+            target_env = "gnu"
+        ))
+    );
+    assert_eq!(
+        cfg_target_matches!("{x86}-*"),
+        cfg!(any(target_arch = "x86", target_arch = "x86_64"))
+    );
+    assert_eq!(
+        cfg_target_matches!("*-*-*-android"),
+        cfg!(target_os = "android")
+    );
+    assert_eq!(
+        cfg_target_matches!("*-*-{apple}"),
+        cfg!(any(
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "tvos",
+            target_os = "watchos"
+        ))
+    );
+}
+
+/// Checks whether `expr` holds for the cfgs `cfgs`, given as key-value pairs
+fn eval(expr: &CfgExpr, cfgs: &[(&str, &str)]) -> bool {
+    match expr {
+        CfgExpr::Cfg(cfg) => cfgs.contains(&(cfg.name, cfg.value.unwrap_or_default())),
+        CfgExpr::All(list) => list.iter().all(|expr| eval(expr, cfgs)),
+        CfgExpr::Any(list) => list.iter().any(|expr| eval(expr, cfgs)),
+        CfgExpr::Not(expr) => !eval(expr, cfgs),
+    }
+}
+
+/// Each pattern, with the predicate of `#[cfg_target]` for it
+macro_rules! predicates {
+    ($($pat:literal),* $(,)?) => {
+        [$(($pat, target_tuples::__cfg_target_predicate!($pat))),*]
+    };
+}
+
+/// Checks that the predicates agree with [`TargetPattern`] on every target in `rustc-cfgs.data`
+fn check_rustc_targets(preds: &[(&str, &str)]) -> std::io::Result<()> {
+    let f = BufReader::new(File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/rustc-cfgs.data"
+    ))?);
+    for s in f.lines() {
+        let s = s?;
+        let s = s.trim();
+        if s.is_empty() || s.starts_with('#') {
+            continue;
+        }
+        let fields = s.split('|').collect::<Vec<_>>();
+
+        // A target whose system is not known to the library is only checked by the patterns of an architecture
+        let (targ, arch_only) = match TargetRef::try_parse(fields[0]) {
+            Ok(targ) => (*targ, false),
+            Err(_) => match fields[0].split('-').next().unwrap().parse() {
+                Ok(arch) => {
+                    let sys = System::from_os(OS::Unknown);
+                    (
+                        CanonicalTarget {
+                            arch,
+                            vendor: None,
+                            sys,
+                        },
+                        true,
+                    )
+                }
+                Err(_) => continue,
+            },
+        };
+
+        // Skip targets whose name does not say everything rustc knows of them, such as `aarch64-unknown-linux-gnu_ilp32`
+        let cfgs = targ.rustc_cfgs().target;
+        let features = cfgs.features.join(",");
+        let mut described = vec![
+            (cfgs.arch, fields[1]),
+            (cfgs.endian, fields[6]),
+            (cfgs.pointer_width, fields[7]),
+        ];
+        if !arch_only {
+            described.extend([
+                (cfgs.os, fields[3]),
+                (cfgs.env, fields[4]),
+                (cfgs.abi, fields[5]),
+                (&features, fields[8]),
+            ]);
+            if targ.vendor.is_some() {
+                described.push((cfgs.vendor, fields[2]));
+            }
+        }
+        if described.iter().any(|(ours, rustc)| ours != rustc) {
+            continue;
+        }
+
+        // rustc gives a target without a vendor the vendor `unknown`
+        let targ = CanonicalTarget {
+            vendor: Some(targ.vendor.unwrap_or(Vendor::Unknown)),
+            ..targ
+        };
+
+        // Levels of 32-bit x86 are not checked, so `i586` targets match `i686` patterns
+        if let Architecture::X86_32(..=5) = targ.arch {
+            continue;
+        }
+
+        let mut cfgs = vec![
+            ("target_arch", fields[1]),
+            ("target_vendor", fields[2]),
+            ("target_os", fields[3]),
+            ("target_env", fields[4]),
+            ("target_abi", fields[5]),
+            ("target_endian", fields[6]),
+            ("target_pointer_width", fields[7]),
+        ];
+        cfgs.extend(
+            fields[8]
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .map(|feature| ("target_feature", feature)),
+        );
+
+        for (pat, pred) in preds {
+            if arch_only && pat.split('-').count() > 2 {
+                continue;
+            }
+
+            assert_eq!(
+                eval(&CfgExpr::parse(pred).unwrap(), &cfgs),
+                pat.parse::<TargetPattern>().unwrap().matches(&targ),
+                "{pat} on {}: {pred}",
+                fields[0]
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_rustc_targets() -> std::io::Result<()> {
+    check_rustc_targets(&predicates![
+        "*",
+        "{x86}-*",
+        "{x86_64+}-*",
+        "{x86_64v2+}-*",
+        "{i686+}-*",
+        "{arm}-*",
+        "aarch64-*",
+        "powerpc64-*",
+        "*-unknown-*",
+        "*-pc-*",
+        "*-apple-*",
+        "*-*-linux",
+        "*-*-linux-gnu",
+        "*-*-linux-musl",
+        "*-*-linux-*",
+        "*-*-none",
+        "*-*-*-android",
+        "*-*-*-gnu",
+        "*-*-*-eabi",
+        "*-*-*-eabihf",
+        "*-*-{bsd}",
+        "*-*-{apple}",
+        "*-*-windows-msvc",
+        "x86_64-*-linux-gnu",
+        "x86_64-*-linux-gnux32",
+        "arm-*-linux-gnu",
+        "arm-*-linux-gnueabihf",
+        "powerpc64-*-linux-gnu",
+        "powerpc64le-*-linux-gnu",
+        "aarch64-apple-darwin",
+    ])
+}
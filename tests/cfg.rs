@@ -0,0 +1,392 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use target_tuples::cfg::{parse_print_cfg, Cfg, CfgExpr, TargetCfg};
+use target_tuples::pieces::Architecture;
+use target_tuples::{CanonicalTarget, TargetRef};
+
+/// Targets whose rustc name carries information the library does not parse: the `uwp` vendor, the `spe` and `ilp32` ABIs,
+///  and MIPS release 6. The iOS and tvOS simulators on x86 are also described by their cfgs, but not by their names
+const LOSSY_NAMES: &[&str] = &[
+    "aarch64-uwp-windows-msvc",
+    "i686-uwp-windows-gnu",
+    "i686-uwp-windows-msvc",
+    "x86_64-uwp-windows-gnu",
+    "x86_64-uwp-windows-msvc",
+    "powerpc-unknown-linux-gnuspe",
+    "aarch64-unknown-linux-gnu_ilp32",
+    "aarch64_be-unknown-linux-gnu_ilp32",
+    "mipsisa32r6-unknown-linux-gnu",
+    "mipsisa32r6el-unknown-linux-gnu",
+    "mipsisa64r6-unknown-linux-gnuabi64",
+    "mipsisa64r6el-unknown-linux-gnuabi64",
+    "i386-apple-ios",
+    "x86_64-apple-ios",
+    "x86_64-apple-tvos",
+];
+
+/// Targets whose name says less than their cfgs: the iOS and tvOS simulators on x86,
+///  and x86_64 Android, which requires x86-64-v2
+const MORE_PRECISE_THAN_NAME: &[&str] = &[
+    "i386-apple-ios",
+    "x86_64-apple-ios",
+    "x86_64-apple-tvos",
+    "x86_64-linux-android",
+];
+
+/// Targets in `rustc-cfgs.data` whose names the library does not parse, mostly because they name a version of their architecture
+///  (such as `armv7` or `riscv64gc`), or a system or vendor it does not know (such as `uefi` or `wrs`)
+const UNPARSED_NAMES: &[&str] = &[
+    "aarch64-apple-ios-sim",
+    "aarch64-unknown-none-softfloat",
+    "aarch64-unknown-redox",
+    "aarch64-unknown-uefi",
+    "aarch64-wrs-vxworks",
+    "armebv7r-none-eabi",
+    "armebv7r-none-eabihf",
+    "armv4t-unknown-linux-gnueabi",
+    "armv5te-unknown-linux-gnueabi",
+    "armv5te-unknown-linux-musleabi",
+    "armv5te-unknown-linux-uclibceabi",
+    "armv6-unknown-freebsd",
+    "armv6-unknown-netbsd-eabihf",
+    "armv7-linux-androideabi",
+    "armv7-unknown-freebsd",
+    "armv7-unknown-linux-gnueabi",
+    "armv7-unknown-linux-gnueabihf",
+    "armv7-unknown-linux-musleabi",
+    "armv7-unknown-linux-musleabihf",
+    "armv7-unknown-netbsd-eabihf",
+    "armv7-wrs-vxworks-eabihf",
+    "armv7a-none-eabi",
+    "armv7a-none-eabihf",
+    "armv7r-none-eabi",
+    "armv7r-none-eabihf",
+    "armv7s-apple-ios",
+    "bpfeb-unknown-none",
+    "bpfel-unknown-none",
+    "hexagon-unknown-linux-musl",
+    "i686-unknown-uefi",
+    "i686-wrs-vxworks",
+    "mipsel-sony-psp",
+    "msp430-none-elf",
+    "nvptx64-nvidia-cuda",
+    "powerpc-wrs-vxworks",
+    "powerpc-wrs-vxworks-spe",
+    "powerpc64-wrs-vxworks",
+    "riscv32gc-unknown-linux-gnu",
+    "riscv32gc-unknown-linux-musl",
+    "riscv32i-unknown-none-elf",
+    "riscv32imac-unknown-none-elf",
+    "riscv32imc-esp-espidf",
+    "riscv32imc-unknown-none-elf",
+    "riscv64gc-unknown-linux-gnu",
+    "riscv64gc-unknown-linux-musl",
+    "riscv64gc-unknown-none-elf",
+    "riscv64imac-unknown-none-elf",
+    "s390x-unknown-linux-gnu",
+    "s390x-unknown-linux-musl",
+    "thumbv4t-none-eabi",
+    "thumbv6m-none-eabi",
+    "thumbv7a-pc-windows-msvc",
+    "thumbv7a-uwp-windows-msvc",
+    "thumbv7em-none-eabi",
+    "thumbv7em-none-eabihf",
+    "thumbv7m-none-eabi",
+    "thumbv7neon-linux-androideabi",
+    "thumbv7neon-unknown-linux-gnueabihf",
+    "thumbv7neon-unknown-linux-musleabihf",
+    "thumbv8m.base-none-eabi",
+    "thumbv8m.main-none-eabi",
+    "thumbv8m.main-none-eabihf",
+    "wasm32-unknown-unknown",
+    "wasm64-unknown-unknown",
+    "x86_64-fortanix-unknown-sgx",
+    "x86_64-unknown-illumos",
+    "x86_64-unknown-l4re-uclibc",
+    "x86_64-unknown-redox",
+    "x86_64-unknown-uefi",
+];
+
+/// Parses a target in `rustc-cfgs.data`. Every name except those in [`UNPARSED_NAMES`] must parse
+fn parse_name(name: &str) -> Option<TargetRef<'_>> {
+    let targ = TargetRef::try_parse(name).ok();
+
+    assert_eq!(
+        targ.is_some(),
+        !UNPARSED_NAMES.contains(&name),
+        "{name} is listed in UNPARSED_NAMES if and only if it does not parse"
+    );
+
+    targ
+}
+
+#[test]
+fn test_rustc_cfgs() -> std::io::Result<()> {
+    let f = BufReader::new(File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/rustc-cfgs.data"
+    ))?);
+    for s in f.lines() {
+        let s = s?;
+        let s = s.trim();
+        if s.is_empty() || s.starts_with('#') {
+            continue;
+        }
+        let fields = s.split('|').collect::<Vec<_>>();
+        let features = fields[8]
+            .split(',')
+            .filter(|f| !f.is_empty())
+            .collect::<Vec<_>>();
+        let cfg = TargetCfg {
+            arch: fields[1],
+            vendor: fields[2],
+            os: fields[3],
+            env: fields[4],
+            abi: fields[5],
+            endian: fields[6],
+            pointer_width: fields[7],
+            features: &features,
+        };
+
+        let targ = cfg.to_target();
+
+        // Targets the library cannot parse are still converted, but there is nothing to compare them with
+        let Some(expected) = parse_name(fields[0]) else {
+            continue;
+        };
+
+        if MORE_PRECISE_THAN_NAME.contains(&fields[0]) {
+            continue;
+        }
+
+        assert_eq!(targ.arch, expected.arch, "{}", fields[0]);
+        assert_eq!(targ.sys, expected.sys, "{}", fields[0]);
+        if expected.vendor.is_some() {
+            assert_eq!(targ.vendor, expected.vendor, "{}", fields[0]);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_current() {
+    const CURRENT: CanonicalTarget = CanonicalTarget::CURRENT;
+
+    assert_eq!(CURRENT, TargetCfg::CURRENT.to_target());
+
+    if cfg!(all(
+        target_arch = "x86_64",
+        target_os = "linux",
+        target_env = "gnu"
+    )) && TargetCfg::CURRENT.abi.is_empty()
+    {
+        assert_eq!(CURRENT.to_string(), "x86_64-unknown-linux-gnu");
+    }
+}
+
+#[test]
+fn test_generated_cfgs() -> std::io::Result<()> {
+    let f = BufReader::new(File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/rustc-cfgs.data"
+    ))?);
+    for s in f.lines() {
+        let s = s?;
+        let s = s.trim();
+        if s.is_empty() || s.starts_with('#') {
+            continue;
+        }
+        let fields = s.split('|').collect::<Vec<_>>();
+
+        let Some(targ) = parse_name(fields[0]) else {
+            continue;
+        };
+
+        if LOSSY_NAMES.contains(&fields[0]) {
+            continue;
+        }
+
+        let cfgs = targ.rustc_cfgs();
+        let t = cfgs.target;
+        let sorted = |list: &[&str]| {
+            let mut list = list.to_vec();
+            list.sort_unstable();
+            list.join(",")
+        };
+        let got = [
+            t.arch,
+            if targ.vendor.is_some() {
+                t.vendor
+            } else {
+                fields[2]
+            },
+            t.os,
+            t.env,
+            t.abi,
+            t.endian,
+            t.pointer_width,
+            &sorted(cfgs.families),
+            &sorted(cfgs.has_atomic),
+        ];
+        let expected = [
+            fields[1], fields[2], fields[3], fields[4], fields[5], fields[6], fields[7], fields[9],
+            fields[10],
+        ];
+        assert_eq!(got, expected, "{}", fields[0]);
+
+        // The generated cfgs describe the same target
+        let mut roundtrip = cfgs.target.to_target();
+        if targ.vendor.is_none() {
+            roundtrip.vendor = None;
+        }
+        assert_eq!(roundtrip, *targ, "{}", fields[0]);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_cfg_iter() {
+    let cfgs = TargetRef::parse("x86_64-pc-windows-msvc").rustc_cfgs();
+    assert!(cfgs.contains("windows", None));
+    assert!(!cfgs.contains("unix", None));
+    assert!(cfgs.contains("target_family", Some("windows")));
+    assert!(cfgs.contains("target_has_atomic", Some("128")));
+    assert!(cfgs.contains("target_env", Some("msvc")));
+
+    let cfgs = TargetRef::parse("wasm32-unknown-emscripten").rustc_cfgs();
+    assert!(cfgs.contains("unix", None));
+    assert!(cfgs.contains("target_family", Some("wasm")));
+    assert!(cfgs.contains("target_abi", Some("")));
+}
+
+const X86_64_LINUX_GNU: &str = r#"debug_assertions
+panic="unwind"
+target_abi=""
+target_arch="x86_64"
+target_endian="little"
+target_env="gnu"
+target_family="unix"
+target_feature="fxsr"
+target_feature="sse"
+target_feature="sse2"
+target_has_atomic="16"
+target_has_atomic="32"
+target_has_atomic="64"
+target_has_atomic="8"
+target_has_atomic="ptr"
+target_os="linux"
+target_pointer_width="64"
+target_vendor="unknown"
+unix
+"#;
+
+#[test]
+fn test_print_cfg() {
+    let printed = parse_print_cfg(X86_64_LINUX_GNU).unwrap();
+    assert_eq!(
+        printed.target,
+        TargetRef::parse("x86_64-unknown-linux-gnu").canonical()
+    );
+    assert_eq!(printed.unaccounted, vec![]);
+
+    // A custom target, with an operating system the library does not know
+    let custom = X86_64_LINUX_GNU
+        .replace("\"linux\"", "\"mykernel\"")
+        .replace("unix\n", "");
+    let printed = parse_print_cfg(&custom).unwrap();
+    assert_eq!(printed.target.arch, Architecture::X86_64 { microarch: 1 });
+    assert_eq!(
+        printed.unaccounted,
+        vec![
+            Cfg {
+                name: "target_family",
+                value: Some("unix")
+            },
+            Cfg {
+                name: "target_os",
+                value: Some("mykernel")
+            }
+        ]
+    );
+    assert_eq!(printed.unaccounted[1].to_string(), "target_os=\"mykernel\"");
+
+    assert!(parse_print_cfg("target_os=linux").is_err());
+    assert!(parse_print_cfg("target_os=\"linux\"").is_err());
+}
+
+#[test]
+fn test_print_cfg_host() {
+    let Ok(out) = std::process::Command::new(std::env::var("RUSTC").unwrap_or("rustc".into()))
+        .args(["--print", "cfg"])
+        .output()
+    else {
+        return;
+    };
+    if !out.status.success() {
+        return;
+    }
+
+    let text = String::from_utf8(out.stdout).unwrap();
+    let printed = parse_print_cfg(&text).unwrap();
+    assert_eq!(printed.target, CanonicalTarget::CURRENT);
+}
+
+#[test]
+fn test_cfg_expr() {
+    let linux = TargetRef::parse("x86_64-unknown-linux-gnu");
+    let windows = TargetRef::parse("i686-pc-windows-msvc");
+
+    let expr = CfgExpr::parse(r#"cfg(all(unix, target_arch = "x86_64"))"#).unwrap();
+    assert_eq!(expr.to_string(), r#"all(unix, target_arch="x86_64")"#);
+    assert_eq!(expr.eval(&linux).matches, Some(true));
+    assert_eq!(expr.eval(&windows).matches, Some(false));
+    assert_eq!(expr.eval(&linux).unknown, vec![]);
+
+    let expr = CfgExpr::parse(r#"any(windows, target_env = "musl",)"#).unwrap();
+    assert_eq!(expr.eval(&linux).matches, Some(false));
+    assert_eq!(expr.eval(&windows).matches, Some(true));
+
+    let expr = CfgExpr::parse("not(target_has_atomic = \"64\")").unwrap();
+    assert_eq!(expr.eval(&linux).matches, Some(false));
+
+    // Cfgs that do not depend on the target only matter if they decide the result
+    let expr = CfgExpr::parse(r#"all(unix, feature = "std")"#).unwrap();
+    let eval = expr.eval(&linux);
+    assert_eq!(eval.matches, None);
+    assert_eq!(
+        eval.unknown,
+        vec![Cfg {
+            name: "feature",
+            value: Some("std")
+        }]
+    );
+    assert_eq!(expr.eval(&windows).matches, Some(false));
+
+    let expr = CfgExpr::parse("any(windows, debug_assertions)").unwrap();
+    assert_eq!(expr.eval(&windows).matches, Some(true));
+    assert_eq!(expr.eval(&linux).matches, None);
+
+    assert_eq!(
+        CfgExpr::parse("all()").unwrap().eval(&linux).matches,
+        Some(true)
+    );
+    assert_eq!(
+        CfgExpr::parse("any()").unwrap().eval(&linux).matches,
+        Some(false)
+    );
+
+    for invalid in [
+        "",
+        "all(unix",
+        "not(unix, windows)",
+        "maybe(unix)",
+        "target_os = linux",
+        "unix windows",
+        "cfg(unix, windows)",
+    ] {
+        assert!(CfgExpr::parse(invalid).is_err(), "{}", invalid);
+    }
+}
@@ -0,0 +1,215 @@
+#![cfg(unix)]
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::process::Command;
+
+const ARCHES: &[&str] = &[
+    "i386",
+    "i486",
+    "i586",
+    "i686",
+    "x86_64",
+    "aarch64",
+    "aarch64_be",
+    "arm",
+    "armeb",
+    "mips",
+    "mipsel",
+    "mips64",
+    "mips64el",
+    "powerpc",
+    "powerpc64",
+    "powerpc64le",
+    "riscv32",
+    "riscv64",
+    "sparc",
+    "sparcv9",
+    "m68k",
+];
+
+const VENDORS: &[&str] = &["", "unknown-", "pc-", "apple-", "ibm-"];
+
+const SYSTEMS: &[&str] = &[
+    "linux",
+    "linux-gnu",
+    "linux-gnueabi",
+    "linux-gnueabihf",
+    "linux-gnux32",
+    "linux-musl",
+    "linux-android",
+    "linux-uclibc",
+    "uclinux-uclibc",
+    "kfreebsd-gnu",
+    "knetbsd-gnu",
+    "netbsd-eabi",
+    "nto-qnx",
+    "freebsd",
+    "netbsd",
+    "openbsd",
+    "dragonfly",
+    "darwin",
+    "ios",
+    "macos",
+    "haiku",
+    "solaris",
+    "aix",
+    "fuchsia",
+    "rtems",
+    "cloudabi",
+    "elf",
+    "eabi",
+    "eabihf",
+    "none",
+    "none-elf",
+    "none-eabi",
+    "windows",
+];
+
+/// Runs the config.sub script shipped with the library on `input`
+fn run_gnu(input: &str) -> Option<String> {
+    let out = Command::new("sh")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/config.sub"))
+        .arg(input)
+        .output()
+        .expect("Could not run config.sub");
+
+    if out.status.success() {
+        Some(String::from_utf8(out.stdout).unwrap().trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Runs `config-sub --gnu` on `input`
+fn run_ours(input: &str) -> Option<String> {
+    let out = Command::new(env!("CARGO_BIN_EXE_config-sub"))
+        .arg("--gnu")
+        .arg(input)
+        .output()
+        .expect("Could not run config-sub");
+
+    if out.status.success() {
+        Some(String::from_utf8(out.stdout).unwrap().trim().to_string())
+    } else {
+        None
+    }
+}
+
+/// Why config.sub and the library are expected to disagree on whether a generated input is valid
+fn generated_reason(vendor: &str, sys: &str) -> Option<String> {
+    match (vendor, sys) {
+        ("", _) => None,
+        (_, "none-elf" | "none-eabi") => Some(
+            "config.sub reads `none` after a vendor as part of the machine, rather than as the operating system"
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+/// The inputs, along with the reason config.sub and the library may disagree on whether they are valid
+fn corpus() -> std::io::Result<Vec<(String, Option<String>)>> {
+    let mut inputs = Vec::new();
+
+    for arch in ARCHES {
+        for vendor in VENDORS {
+            for sys in SYSTEMS {
+                inputs.push((
+                    format!("{arch}-{vendor}{sys}"),
+                    generated_reason(vendor, sys),
+                ));
+            }
+        }
+    }
+
+    let f = BufReader::new(File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/gnu-compat.data"
+    ))?);
+    for s in f.lines() {
+        let s = s?;
+        let s = s.trim();
+        if s.is_empty() || s.starts_with('#') {
+            continue;
+        }
+        match s.split_once('|') {
+            Some((input, reason)) => inputs.push((input.to_string(), Some(reason.to_string()))),
+            None => inputs.push((s.to_string(), None)),
+        }
+    }
+
+    Ok(inputs)
+}
+
+#[test]
+fn test_gnu_compat() -> std::io::Result<()> {
+    let inputs = corpus()?;
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = (inputs.len() + threads - 1) / threads;
+
+    let results = std::thread::scope(|scope| {
+        let handles = inputs
+            .chunks(chunk)
+            .map(|inputs| {
+                scope.spawn(move || {
+                    inputs
+                        .iter()
+                        .map(|(input, reason)| (input, reason, run_gnu(input), run_ours(input)))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    let mut mismatches = 0;
+    let mut gnu_only = 0;
+    let mut ours_only = 0;
+    let mut unexplained = 0;
+
+    for (input, reason, gnu, ours) in &results {
+        let accepted_by = match (gnu, ours) {
+            (Some(gnu), Some(ours)) if gnu != ours => {
+                eprintln!("mismatch: {input}: config.sub gives {gnu}, we give {ours}");
+                mismatches += 1;
+                continue;
+            }
+            (Some(gnu), None) => {
+                gnu_only += 1;
+                format!("config.sub, as {gnu}")
+            }
+            (None, Some(ours)) => {
+                ours_only += 1;
+                format!("us, as {ours}")
+            }
+            _ => continue,
+        };
+
+        match reason {
+            Some(reason) => eprintln!("note: {input}: accepted only by {accepted_by} ({reason})"),
+            None => {
+                eprintln!("unexplained: {input}: accepted only by {accepted_by}");
+                unexplained += 1;
+            }
+        }
+    }
+
+    eprintln!(
+        "{} inputs, {mismatches} mismatches, {gnu_only} accepted only by config.sub, {ours_only} accepted only by us, {unexplained} of them without a reason",
+        results.len()
+    );
+
+    assert_eq!(mismatches, 0);
+    assert_eq!(
+        unexplained, 0,
+        "inputs accepted by only one of config.sub and the library need a reason in gnu-compat.data"
+    );
+
+    Ok(())
+}
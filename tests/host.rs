@@ -0,0 +1,147 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use target_tuples::host::{ElfInfo, HostProbe};
+use target_tuples::CanonicalTarget;
+
+struct RecordedProbe {
+    sysname: String,
+    machine: String,
+    release: String,
+    cpuinfo: Option<String>,
+    elf: Option<ElfInfo>,
+}
+
+impl HostProbe for RecordedProbe {
+    fn sysname(&self) -> Option<String> {
+        Some(self.sysname.clone())
+    }
+
+    fn machine(&self) -> Option<String> {
+        Some(self.machine.clone())
+    }
+
+    fn release(&self) -> Option<String> {
+        Some(self.release.clone())
+    }
+
+    fn cpuinfo(&self) -> Option<String> {
+        self.cpuinfo.clone()
+    }
+
+    fn shell_elf(&self) -> Option<ElfInfo> {
+        self.elf.clone()
+    }
+}
+
+#[test]
+fn test_recorded_probes() -> std::io::Result<()> {
+    let f = BufReader::new(File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/host-probes.data"
+    ))?);
+    for s in f.lines() {
+        let s = s?;
+        let s = s.trim();
+        if s.is_empty() || s.starts_with('#') {
+            continue;
+        }
+        let fields = s.split('|').collect::<Vec<_>>();
+        let [sysname, machine, release, elf, interp, features, expected] = fields[..] else {
+            panic!("Malformed probe {s}");
+        };
+
+        let elf = match elf {
+            "" | "-" => None,
+            elf => {
+                let (class, e_machine) = match elf.split_once('/') {
+                    Some((class, e_machine)) => (class, e_machine.parse().unwrap()),
+                    None => (elf, 0),
+                };
+
+                Some(ElfInfo {
+                    is_64bit: class.starts_with("64"),
+                    big_endian: class.ends_with("be"),
+                    machine: e_machine,
+                    interpreter: Some(interp.to_string()).filter(|interp| !interp.is_empty()),
+                })
+            }
+        };
+
+        let probe = RecordedProbe {
+            sysname: sysname.to_string(),
+            machine: machine.to_string(),
+            release: release.to_string(),
+            cpuinfo: Some(format!("processor\t: 0\nFeatures\t: {features}\n"))
+                .filter(|_| !features.is_empty()),
+            elf,
+        };
+
+        let targ = CanonicalTarget::guess_host_with(&probe);
+
+        if expected == "!" {
+            assert!(
+                targ.is_err(),
+                "{s}: expected no guess, got {}",
+                targ.unwrap()
+            );
+        } else {
+            assert_eq!(targ.unwrap().to_string(), expected, "{s}");
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_parse_own_elf() -> std::io::Result<()> {
+    let exe = std::fs::read(std::env::current_exe()?)?;
+    let elf = ElfInfo::parse(&exe).unwrap();
+
+    assert_eq!(elf.is_64bit, cfg!(target_pointer_width = "64"));
+    assert_eq!(elf.big_endian, cfg!(target_endian = "big"));
+    if cfg!(target_arch = "x86_64") {
+        assert_eq!(elf.machine, 62);
+    }
+    assert!(elf.interpreter.is_some());
+
+    Ok(())
+}
+
+/// A little-endian ELF64 header with one program header at `phoff`, followed by that program header
+fn elf64_with_interp(phoff: u64, interp_offset: u64, interp_size: u64) -> Vec<u8> {
+    let mut elf = vec![0; 64 + 56];
+    elf[..6].copy_from_slice(b"\x7fELF\x02\x01");
+    elf[18..20].copy_from_slice(&62u16.to_le_bytes());
+    elf[32..40].copy_from_slice(&phoff.to_le_bytes());
+    elf[54..56].copy_from_slice(&56u16.to_le_bytes());
+    elf[56..58].copy_from_slice(&1u16.to_le_bytes());
+
+    elf[64..68].copy_from_slice(&3u32.to_le_bytes());
+    elf[72..80].copy_from_slice(&interp_offset.to_le_bytes());
+    elf[96..104].copy_from_slice(&interp_size.to_le_bytes());
+
+    elf
+}
+
+#[test]
+fn test_parse_malformed_elf() {
+    let elf = ElfInfo::parse(&elf64_with_interp(64, 0, 4)).unwrap();
+    assert_eq!(elf.machine, 62);
+    assert_eq!(elf.interpreter.as_deref(), Some("\x7fELF"));
+
+    assert_eq!(ElfInfo::parse(&elf64_with_interp(u64::MAX - 2, 0, 4)), None);
+    assert_eq!(
+        ElfInfo::parse(&elf64_with_interp(64, u64::MAX - 2, 4)),
+        None
+    );
+    assert_eq!(ElfInfo::parse(&elf64_with_interp(64, 4, u64::MAX)), None);
+}
+
+#[test]
+#[cfg(all(target_os = "linux", target_env = "gnu", target_arch = "x86_64"))]
+fn test_guess_host() {
+    let targ = CanonicalTarget::guess_host().unwrap();
+    assert_eq!(targ.to_string(), "x86_64-pc-linux-gnu");
+}
@@ -1,4 +1,8 @@
-use target_tuples::{match_targets, TargetRef};
+use target_tuples::{
+    match_targets,
+    pieces::{Architecture, Vendor},
+    target, CanonicalTarget, TargetRef,
+};
 
 const TARGET_STRINGS: &[&str] = &[
     "x86_64-pc-linux-gnu",
@@ -58,3 +62,200 @@ pub fn match_funny_targets() {
         }
     }
 }
+
+#[test]
+fn or_patterns() {
+    for (name, targ) in TARGET_STRINGS
+        .iter()
+        .copied()
+        .map(|v| (v, TargetRef::parse(v)))
+    {
+        let mut matched = "";
+        match_targets! {
+            targ {
+                x86_64-pc-linux-gnu | x86_64-*-linux-musl | x86_64-unknown-linux-gnux32 => matched = "linux",
+                i386-* | i486-* => matched = "old x86",
+                w65-*-snes-elf | wc65c816-* => matched = "65816",
+                * => {}
+            }
+        }
+
+        let expected = match name {
+            "x86_64-pc-linux-gnu" | "x86_64-linux-musl" | "x86_64-unknown-linux-gnux32" => "linux",
+            "i386-lilium-kernel" | "i486-windows-msvc" => "old x86",
+            "wc65c816-elf" | "w65-snes-elf" => "65816",
+            _ => "",
+        };
+        assert_eq!(matched, expected, "{name}");
+    }
+}
+
+#[test]
+fn guards_and_bindings() {
+    for (name, targ) in TARGET_STRINGS
+        .iter()
+        .copied()
+        .map(|v| (v, TargetRef::parse(v)))
+    {
+        let mut matched = String::new();
+        match_targets! {
+            targ {
+                arch @ *-*-linux-gnu | arch @ *-*-linux-musl if arch.family() == "x86" => {
+                    matched = format!("{arch} linux");
+                }
+                *-vendor @ *-os @ *-env @ * if name.starts_with('i') => {
+                    matched = format!("{vendor} {os} {env:?}");
+                }
+                arch @ *-vendor @ * if vendor == Vendor::WDC => matched = format!("{arch} wdc"),
+                * => {}
+            }
+        }
+
+        let expected = match name {
+            "x86_64-pc-linux-gnu" | "x86_64-linux-musl" => "x86_64 linux",
+            "i386-lilium-kernel" => "pc lilium Some(Kernel)",
+            "i486-windows-msvc" => "pc windows Some(MSVC)",
+            "wc65c816-elf" | "w65-snes-elf" => "w65 wdc",
+            _ => "",
+        };
+        assert_eq!(matched, expected, "{name}");
+    }
+}
+
+#[test]
+fn families() {
+    for name in [
+        "x86_64-unknown-freebsd",
+        "i686-unknown-netbsd",
+        "i386-unknown-openbsd",
+        "x86_64v3-apple-darwin",
+        "arm-apple-ios",
+        "aarch64-apple-darwin",
+        "x86_64-pc-linux-gnu",
+        "sparcv9-sun-solaris",
+    ] {
+        let targ = TargetRef::parse(name);
+        let mut matched = String::new();
+        match_targets! {
+            targ {
+                {x86_64v2+}-*-{apple} => matched = String::from("modern x86 apple"),
+                {i686+}-*-os @ {bsd} => matched = format!("i686 {os}"),
+                {x86}-*-{bsd} => matched = String::from("x86 bsd"),
+                arch @ {arm}-*-{apple}-* => matched = format!("{arch} apple"),
+                *-*-{solaris} => matched = String::from("solaris"),
+                * => {}
+            }
+        }
+
+        let expected = match name {
+            "x86_64-unknown-freebsd" => "x86 bsd",
+            "i686-unknown-netbsd" => "i686 netbsd",
+            "i386-unknown-openbsd" => "x86 bsd",
+            "x86_64v3-apple-darwin" => "modern x86 apple",
+            "arm-apple-ios" => "arm apple",
+            "sparcv9-sun-solaris" => "solaris",
+            _ => "",
+        };
+        assert_eq!(matched, expected, "{name}");
+    }
+}
+
+#[test]
+fn require_wildcard() {
+    for name in TARGET_STRINGS {
+        let targ = TargetRef::parse(name);
+        let mut linux = false;
+        match_targets! {
+            #[require_wildcard]
+            targ {
+                *-*-linux-* => linux = true,
+                * => {}
+            }
+        }
+
+        assert_eq!(linux, name.contains("linux"), "{name}");
+    }
+}
+
+#[test]
+fn string_patterns() {
+    for name in [
+        "6502-nes-elf",
+        "65816-snes-elf",
+        "x86_64-apple-darwin21.0",
+        "x86_64-mycorp-linux-gnu",
+        "x86_64-pc-linux-gnu",
+    ] {
+        let targ = TargetRef::parse(name);
+        let mut matched = "";
+        match_targets! {
+            targ {
+                "6502-*-nes-elf" => matched = "nes",
+                "65816-*-snes-elf" | "6502-*-snes-elf" => matched = "snes",
+                "{x86}-*-darwin21.0" => matched = "darwin",
+                "*-r#mycorp-*" if name.contains("linux") => matched = "mycorp",
+                x86_64-pc-linux-gnu => matched = "linux",
+                * => {}
+            }
+        }
+
+        let expected = match name {
+            "6502-nes-elf" => "nes",
+            "65816-snes-elf" => "snes",
+            "x86_64-apple-darwin21.0" => "darwin",
+            "x86_64-mycorp-linux-gnu" => "mycorp",
+            "x86_64-pc-linux-gnu" => "linux",
+            _ => "",
+        };
+        assert_eq!(matched, expected, "{name}");
+    }
+}
+
+#[test]
+fn strings() {
+    for name in [
+        "x86_64-pc-linux-gnu",
+        "aarch64-apple-darwin",
+        "x86_64-pc-notanos",
+        "",
+    ] {
+        let owned = String::from(name);
+        let matched;
+        match_targets! {
+            owned {
+                *-*-linux-* => matched = "linux",
+                !unparsed(_) => matched = "unparsed",
+                * => matched = "other",
+            }
+        }
+
+        let expected = match name {
+            "x86_64-pc-linux-gnu" => "linux",
+            "aarch64-apple-darwin" => "other",
+            _ => "unparsed",
+        };
+        assert_eq!(matched, expected, "{name}");
+    }
+}
+
+const fn default_align(targ: CanonicalTarget) -> usize {
+    match_targets! {
+        targ {
+            {x86}-*-* if targ.guess_vendor() as u8 == Vendor::PC as u8 => 16,
+            arch @ *-* if matches!(arch, Architecture::Wc65c816 | Architecture::M6502) => 1,
+            * => 8,
+        }
+    }
+}
+
+const LINUX_ALIGN: usize = default_align(target!("x86_64-pc-linux-gnu"));
+const SNES_ALIGN: usize = default_align(target!("w65-snes-elf"));
+const VENDOR: &str = target!("i686-linux-gnu").guess_vendor().canonical_name();
+
+#[test]
+fn const_context() {
+    assert_eq!(LINUX_ALIGN, 16);
+    assert_eq!(SNES_ALIGN, 1);
+    assert_eq!(default_align(target!("aarch64-apple-darwin")), 8);
+    assert_eq!(VENDOR, "pc");
+}
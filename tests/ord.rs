@@ -0,0 +1,93 @@
+use std::collections::{BTreeSet, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+use target_tuples::pieces::Architecture;
+use target_tuples::{target, target_ref, CanonicalTarget};
+
+#[test]
+fn test_arch_order() {
+    let mut arches = [
+        Architecture::X86_64 { microarch: 3 },
+        Architecture::Aarch64,
+        Architecture::X86_32(6),
+        Architecture::Arm,
+        Architecture::X86_64 { microarch: 1 },
+        Architecture::X86_32(3),
+        Architecture::X86_16(0),
+    ];
+    arches.sort();
+
+    assert_eq!(
+        arches,
+        [
+            Architecture::Aarch64,
+            Architecture::Arm,
+            Architecture::X86_16(0),
+            Architecture::X86_32(3),
+            Architecture::X86_32(6),
+            Architecture::X86_64 { microarch: 1 },
+            Architecture::X86_64 { microarch: 3 },
+        ]
+    );
+}
+
+#[test]
+fn test_target_order() {
+    let mut targs = [
+        target!("x86_64-pc-windows-msvc"),
+        target!("x86_64-pc-linux-musl"),
+        target!("i686-pc-linux-gnu"),
+        target!("x86_64-pc-linux-gnu"),
+        target!("x86_64-linux-gnu"),
+        target!("aarch64-apple-darwin"),
+    ];
+    targs.sort();
+
+    assert_eq!(
+        targs,
+        [
+            target!("aarch64-apple-darwin"),
+            target!("i686-pc-linux-gnu"),
+            target!("x86_64-linux-gnu"),
+            target!("x86_64-pc-linux-gnu"),
+            target!("x86_64-pc-linux-musl"),
+            target!("x86_64-pc-windows-msvc"),
+        ]
+    );
+
+    assert!(target_ref!("x86_64-linux-gnu") < target_ref!("x86_64-pc-linux-gnu"));
+    assert!(target_ref!("x86_64-pc-linux-gnu") < target_ref!("x86_64-pc-linux-gnux32"));
+}
+
+/// The ordering must agree with equality, so that sets of targets are the same whether they are ordered or hashed
+#[test]
+fn test_consistent_with_eq() -> std::io::Result<()> {
+    let f = BufReader::new(File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/config-sub.data"
+    ))?);
+
+    let mut targs = Vec::new();
+    for s in f.lines() {
+        let s = s?;
+        for name in s.split('|') {
+            if let Ok(targ) = name.parse::<CanonicalTarget>() {
+                targs.push(targ);
+            }
+        }
+    }
+
+    let ordered = targs.iter().collect::<BTreeSet<_>>();
+    let hashed = targs.iter().collect::<HashSet<_>>();
+    assert_eq!(ordered.len(), hashed.len());
+
+    for a in &targs {
+        for b in &targs {
+            assert_eq!(a == b, a.cmp(b).is_eq(), "{a:?} {b:?}");
+            assert_eq!(a.cmp(b), b.cmp(a).reverse(), "{a:?} {b:?}");
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use target_tuples::pattern::TargetPattern;
+use target_tuples::{match_targets, CanonicalTarget};
+
+const TARGETS: &[&str] = &[
+    "x86_64-pc-linux-gnu",
+    "x86_64-linux-musl",
+    "x86_64-unknown-linux-gnux32",
+    "x86_64v3-pc-linux-gnu",
+    "i386-lilium-kernel",
+    "i486-windows-msvc",
+    "i786-pc-elf",
+    "i686-pc-windows-gnu",
+    "aarch64-apple-darwin",
+    "aarch64-apple-ios-simulator",
+    "aarch64-unknown-linux-gnu",
+    "x86_64-unknown-freebsd",
+    "i686-unknown-netbsd",
+    "x86_64v2-apple-darwin",
+    "arm-apple-ios",
+    "sparcv9-sun-solaris",
+    "x86_64-unknown-linux-gnu",
+    "x86_64-mycorp-linux-gnu",
+    "arm-none-eabi",
+    "m68k-uclinux-uclibc",
+    "wc65c816-elf",
+    "w65-snes-elf",
+    "wasm32-unknown-emscripten",
+];
+
+/// Checks that each pattern matches the same targets at runtime as it does in `match_targets!`
+macro_rules! check_patterns {
+    ($([$($pat:tt)*]),* $(,)?) => {
+        $({
+            let text = stringify!($($pat)*).replace(' ', "");
+            let pattern = text.parse::<TargetPattern>().unwrap();
+
+            assert_eq!(pattern.to_string().parse::<TargetPattern>().unwrap(), pattern, "{text}");
+
+            for name in TARGETS {
+                let targ = name.parse::<CanonicalTarget>().unwrap();
+                let mut expected = false;
+                match_targets! {
+                    targ {
+                        $($pat)* => expected = true,
+                        * => {}
+                    }
+                }
+
+                assert_eq!(pattern.matches(&targ), expected, "{text} on {name}");
+            }
+        })*
+    };
+}
+
+#[test]
+#[allow(deprecated)] // The fallback arm is unreachable for patterns that match every target
+fn test_same_as_macro() {
+    check_patterns![
+        [*],
+        [x86_64-*],
+        [*-*],
+        [*-apple-*],
+        [x86_64-pc-*],
+        [*-*-*],
+        [x86_64-*-linux-*],
+        [x86_64-*-linux-gnu],
+        [x86_64-pc-linux-gnu],
+        [x86_64-*-*-musl],
+        [i386-*-lilium-kernel],
+        [i486-*-windows-msvc],
+        [*-*-windows-*],
+        [i786-pc-elf],
+        [*-*-elf],
+        [w65-*-snes-elf],
+        [aarch64-*-ios-simulator],
+        [arm-*-eabi],
+        [m68k-*-linux-uclibc],
+        [{x86}-*],
+        [{x86}-*-*],
+        [{arm}-*],
+        [{i686+}-*],
+        [{x86_64v2+}-*],
+        [{x86_64v3+}-pc-*],
+        [*-*-{bsd}],
+        [*-*-{apple}],
+        [{arm}-*-{apple}],
+        [{aarch64}-*-{apple}-*],
+        [*-*-{solaris}-*],
+        [{x86}-*-{bsd}-*],
+        [*-unknown-*],
+        [x86_64-unknown-linux-gnu],
+        [x86_64-r#mycorp-linux-gnu],
+        [*-r#mycorp-*],
+    ];
+}
+
+#[test]
+fn test_custom_vendor() {
+    let pattern = "x86_64-r#mycorp-linux-gnu"
+        .parse::<TargetPattern>()
+        .unwrap();
+    assert_eq!(
+        pattern,
+        "x86_64-unknown-linux-gnu".parse::<TargetPattern>().unwrap()
+    );
+
+    let targ = "x86_64-othercorp-linux-gnu"
+        .parse::<CanonicalTarget>()
+        .unwrap();
+    assert!(pattern.matches(&targ));
+}
+
+#[test]
+fn test_display() {
+    for (pattern, display) in [
+        ("*", "*"),
+        ("*-*-*", "*"),
+        ("x86_64-*", "x86_64-*"),
+        ("*-apple-*", "*-apple-*"),
+        ("amd64-*-linux-*", "x86_64-*-linux-*"),
+        ("i786-pc-elf", "i786-pc-elf"),
+        ("{x86}-*", "{x86}-*"),
+        ("{i686+}-*-{bsd}", "{i686+}-*-{bsd}"),
+        ("{arm}-apple-{apple}-*", "{arm}-apple-{apple}-*"),
+    ] {
+        assert_eq!(
+            pattern.parse::<TargetPattern>().unwrap().to_string(),
+            display
+        );
+    }
+}
+
+#[test]
+fn test_invalid_patterns() {
+    for pattern in [
+        "",
+        "x86_64",
+        "x86_64-pc",
+        "notanarch-*",
+        "x86_64-pc-notanos",
+        "x86_64-pc-linux-linux",
+        "x86_64-pc-linux-gnu-elf",
+        "{notafamily}-*",
+        "{arm+}-*",
+        "{x86}+-*",
+        "*-*-{notafamily}",
+        "*-*-linux-{gnu}",
+        "x86_64-aple-darwin",
+        "*-mycorp-*",
+    ] {
+        assert!(pattern.parse::<TargetPattern>().is_err(), "{pattern}");
+    }
+}
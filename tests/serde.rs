@@ -0,0 +1,117 @@
+use serde_test::{
+    assert_de_tokens, assert_de_tokens_error, assert_ser_tokens, assert_tokens, Token,
+};
+
+use target_tuples::pieces::{Architecture, Environment, Kernel, Vendor, OS};
+use target_tuples::structured::Structured;
+use target_tuples::{CanonicalTarget, OwnedTarget, TargetRef};
+
+#[test]
+fn test_pieces() {
+    assert_tokens(&Architecture::Aarch64, &[Token::Str("aarch64")]);
+    assert_tokens(&Vendor::Apple, &[Token::Str("apple")]);
+    assert_tokens(&OS::FreeBSD, &[Token::Str("freebsd")]);
+    assert_tokens(&Environment::GNUEABIHF, &[Token::Str("gnueabihf")]);
+    assert_tokens(&Kernel::Nto, &[Token::Str("nto")]);
+
+    let sys = TargetRef::parse("arm-unknown-linux-musleabi").sys;
+    assert_tokens(&sys, &[Token::Str("linux-musleabi")]);
+
+    assert_de_tokens(
+        &Architecture::X86_64 { microarch: 1 },
+        &[Token::Str("amd64")],
+    );
+    assert_de_tokens_error::<OS>(
+        &[Token::Str("plan10")],
+        "invalid value: string \"plan10\", expected a component of a target tuple",
+    );
+}
+
+#[test]
+fn test_targets() {
+    let targ = TargetRef::parse("x86_64-pc-linux-gnu");
+
+    assert_tokens(&targ.canonical(), &[Token::Str("x86_64-pc-linux-gnu")]);
+    assert_de_tokens(&targ.canonical(), &[Token::Str("amd64-pc-linux-gnu")]);
+
+    // The exact name is kept when deserializing, but the canonical name is serialized
+    let borrowed = TargetRef::parse("x86_64-linux-gnu");
+    assert_de_tokens(&borrowed, &[Token::BorrowedStr("x86_64-linux-gnu")]);
+    assert_ser_tokens(&borrowed, &[Token::Str("x86_64-pc-linux-gnu")]);
+
+    let owned = "x86_64-linux-gnu".parse::<OwnedTarget>().unwrap();
+    assert_de_tokens(&owned, &[Token::String("x86_64-linux-gnu")]);
+    assert_ser_tokens(&owned, &[Token::Str("x86_64-pc-linux-gnu")]);
+
+    assert_de_tokens_error::<CanonicalTarget>(
+        &[Token::Str("x86_64-pc-plan10")],
+        "invalid value: string \"x86_64-pc-plan10\", expected a target tuple",
+    );
+}
+
+#[test]
+fn test_structured() {
+    let targ = TargetRef::parse("x86_64-pc-linux-gnu").canonical();
+    assert_tokens(
+        &Structured(targ),
+        &[
+            Token::Struct {
+                name: "CanonicalTarget",
+                len: 6,
+            },
+            Token::Str("arch"),
+            Token::Str("x86_64"),
+            Token::Str("vendor"),
+            Token::Some,
+            Token::Str("pc"),
+            Token::Str("kernel"),
+            Token::Some,
+            Token::Str("linux"),
+            Token::Str("os"),
+            Token::Some,
+            Token::Str("linux"),
+            Token::Str("env"),
+            Token::Some,
+            Token::Str("gnu"),
+            Token::Str("objfmt"),
+            Token::None,
+            Token::StructEnd,
+        ],
+    );
+
+    for name in [
+        "arm-unknown-linux-gnueabihf",
+        "m68k-uclinux-uclibc",
+        "i386-nto-qnx",
+        "x86_64-linux-android",
+        "riscv32-none-elf",
+        "powerpc-eabi",
+    ] {
+        let targ = TargetRef::try_parse(name).unwrap();
+        let json = serde_json::to_string(&Structured(targ.canonical())).unwrap();
+        let Structured(roundtrip) = serde_json::from_str(&json).unwrap();
+        assert_eq!(roundtrip, targ.canonical(), "{}", json);
+    }
+
+    assert_de_tokens_error::<Structured<CanonicalTarget>>(
+        &[
+            Token::Map { len: None },
+            Token::Str("arch"),
+            Token::Str("x86_64"),
+            Token::Str("vendor"),
+            Token::None,
+            Token::Str("kernel"),
+            Token::Some,
+            Token::Str("kfreebsd"),
+            Token::Str("os"),
+            Token::Some,
+            Token::Str("linux"),
+            Token::Str("env"),
+            Token::None,
+            Token::Str("objfmt"),
+            Token::None,
+            Token::MapEnd,
+        ],
+        "the pieces of the target do not form a valid system",
+    );
+}
@@ -2,7 +2,7 @@ use std::fs::File;
 
 use std::io::{BufRead, BufReader};
 
-use target_tuples::TargetRef;
+use target_tuples::{CanonicalTarget, TargetRef};
 
 #[test]
 fn test_many_config_subs() -> std::io::Result<()> {
@@ -61,3 +61,52 @@ fn test_idempotency() -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_kernel_split() {
+    use target_tuples::pieces::{Environment, Kernel, OS};
+
+    let targ = TargetRef::parse("x86_64-pc-linux-android");
+    assert_eq!(targ.sys.kernel(), Some(Kernel::Linux));
+    assert_eq!(targ.sys.os(), Some(OS::Linux));
+    assert_eq!(targ.sys.env(), Some(Environment::Android));
+
+    let targ = TargetRef::parse("x86_64-pc-kfreebsd-gnu");
+    assert_eq!(targ.sys.kernel(), Some(Kernel::KFreeBSD));
+    assert_eq!(targ.sys.env(), Some(Environment::GNU));
+
+    let targ = TargetRef::parse("i386-nto-qnx");
+    assert_eq!(targ.sys.kernel(), Some(Kernel::Nto));
+    assert_eq!(targ.sys.os(), Some(OS::QNX));
+    assert_eq!(targ.sys.env(), None);
+
+    let targ = TargetRef::parse("x86_64-uclinux-uclibc");
+    assert_eq!(targ.sys.kernel(), Some(Kernel::UCLinux));
+    assert_eq!(targ.sys.os(), Some(OS::Linux));
+
+    let targ = TargetRef::parse("x86_64-pc-linux");
+    assert_eq!(targ.sys.kernel(), None);
+
+    let targ = TargetRef::parse("aarch64-apple-darwin");
+    assert_eq!(targ.sys.kernel(), None);
+}
+
+#[test]
+fn test_legacy_aliases() -> std::io::Result<()> {
+    let f = BufReader::new(File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/alias.data"
+    ))?);
+    for s in f.lines() {
+        let s = s?;
+        if s.trim().is_empty() {
+            continue;
+        }
+        let mut s = s.split('|');
+        let (k, v) = (s.next().unwrap(), s.next().unwrap());
+        let targ = CanonicalTarget::from_alias(k).unwrap();
+        assert_eq!(targ.to_string(), v);
+    }
+
+    Ok(())
+}
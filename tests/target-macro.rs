@@ -0,0 +1,50 @@
+use target_tuples::{target, target_ref, CanonicalTarget, TargetRef};
+
+macro_rules! check_targets {
+    ($($name:literal),* $(,)?) => {
+        $({
+            const TARG: CanonicalTarget = target!($name);
+            assert_eq!(TARG, $name.parse::<CanonicalTarget>().unwrap(), "{}", $name);
+
+            const TARG_REF: TargetRef<'static> = target_ref!($name);
+            assert_eq!(TARG_REF, TargetRef::parse($name), "{}", $name);
+        })*
+    };
+}
+
+#[test]
+fn test_target_macro() {
+    check_targets![
+        "x86_64-pc-linux-gnu",
+        "x86_64-linux-musl",
+        "x86_64v3-unknown-linux-gnu",
+        "i686-pc-windows-msvc",
+        "i386-lilium-kernel",
+        "i786-pc-elf",
+        "aarch64-apple-darwin",
+        "powerpc64le-unknown-linux-gnu",
+        "arm-unknown-linux-gnueabihf",
+        "m68k-uclinux-uclibc",
+        "x86_64-kfreebsd-gnu",
+        "x86_64-unknown-netbsd",
+        "wasm32-unknown-emscripten",
+        "wc65c816-elf",
+        "w65-snes-elf",
+        "riscv64-unknown-none-elf",
+    ];
+}
+
+#[test]
+fn test_target_ref_exact() {
+    const TARG: TargetRef<'static> = target_ref!("x86_64-linux-musl");
+
+    assert_eq!(TARG.exact(), "x86_64-linux-musl");
+    assert_eq!(TARG.canonical(), "x86_64-linux-musl".parse().unwrap());
+}
+
+#[test]
+fn test_target_ref_raw_vendor() {
+    const TARG: TargetRef<'static> = target_ref!("x86_64-r#mycorp-linux-gnu");
+    assert_eq!(TARG.exact(), "x86_64-mycorp-linux-gnu");
+    assert_eq!(TARG.canonical(), target!("x86_64-unknown-linux-gnu"));
+}
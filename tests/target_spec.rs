@@ -0,0 +1,117 @@
+use std::fs;
+
+use target_tuples::spec::{JsonValue, TargetSpec};
+use target_tuples::TargetRef;
+
+const SPECS: &[&str] = &[
+    "aarch64-apple-darwin",
+    "arm-unknown-linux-gnueabihf",
+    "i686-pc-windows-msvc",
+    "mipsel-unknown-linux-gnu",
+    "powerpc64-unknown-linux-gnu",
+    "wasm32-unknown-emscripten",
+    "x86_64-unknown-linux-gnu",
+];
+
+fn read_spec(name: &str) -> String {
+    fs::read_to_string(format!(
+        "{}/tests/target-specs/{}.json",
+        env!("CARGO_MANIFEST_DIR"),
+        name
+    ))
+    .unwrap()
+}
+
+#[test]
+fn test_read_specs() {
+    for name in SPECS {
+        let spec = TargetSpec::parse(&read_spec(name)).unwrap();
+        let expected = TargetRef::parse(name);
+
+        assert_eq!(spec.target.arch, expected.arch, "{}", name);
+        assert_eq!(spec.target.vendor, expected.vendor, "{}", name);
+        assert_eq!(spec.target.sys, expected.sys, "{}", name);
+
+        for key in ["llvm-target", "data-layout", "arch", "os", "env", "vendor"] {
+            assert!(
+                spec.properties.iter().all(|(k, _)| k != key),
+                "{}: {}",
+                name,
+                key
+            );
+        }
+        assert!(spec.data_layout.is_some());
+
+        let written = spec.to_string();
+        assert_eq!(TargetSpec::parse(&written).unwrap(), spec, "{}", name);
+    }
+}
+
+#[test]
+fn test_write_spec() {
+    let spec = TargetSpec::parse(&read_spec("x86_64-unknown-linux-gnu")).unwrap();
+
+    let generated = TargetSpec::from_target(&spec.target);
+    assert_eq!(generated.llvm_target, spec.llvm_target);
+    assert_eq!(generated.data_layout, spec.data_layout);
+
+    let reread = TargetSpec::parse(&generated.to_string()).unwrap();
+    assert_eq!(reread.target, spec.target);
+    assert_eq!(
+        reread
+            .properties
+            .iter()
+            .find(|(k, _)| k == "max-atomic-width"),
+        spec.properties
+            .iter()
+            .find(|(k, _)| k == "max-atomic-width")
+    );
+}
+
+#[test]
+fn test_custom_os() {
+    let json =
+        read_spec("x86_64-unknown-linux-gnu").replace("\"os\": \"linux\"", "\"os\": \"mykernel\"");
+    let spec = TargetSpec::parse(&json).unwrap();
+
+    assert!(spec
+        .properties
+        .contains(&("os".into(), JsonValue::String("mykernel".into()))));
+
+    let written = JsonValue::parse(&spec.to_string()).unwrap();
+    let JsonValue::Object(members) = written else {
+        panic!("target spec is not an object");
+    };
+    let os = members
+        .iter()
+        .filter(|(k, _)| k == "os")
+        .collect::<Vec<_>>();
+    assert_eq!(os, [&("os".into(), JsonValue::String("mykernel".into()))]);
+}
+
+#[test]
+fn test_json() {
+    let value =
+        JsonValue::parse(r#" { "a": [1, -2.5e3, true, null], "b\u00e9\n": "\ud83d\ude00" } "#)
+            .unwrap();
+    assert_eq!(
+        value,
+        JsonValue::Object(vec![
+            (
+                "a".into(),
+                JsonValue::Array(vec![
+                    JsonValue::Number("1".into()),
+                    JsonValue::Number("-2.5e3".into()),
+                    JsonValue::Bool(true),
+                    JsonValue::Null
+                ])
+            ),
+            ("b\u{e9}\n".into(), JsonValue::String("\u{1f600}".into())),
+        ])
+    );
+    assert_eq!(JsonValue::parse(&value.to_string()).unwrap(), value);
+
+    for invalid in ["", "{", "[1,]", "{\"a\" 1}", "\"\\x\"", "tru", "[1] 2"] {
+        assert!(JsonValue::parse(invalid).is_err(), "{}", invalid);
+    }
+}